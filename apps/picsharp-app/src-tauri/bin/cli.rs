@@ -1,8 +1,28 @@
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use picsharp_lib::backup::BackupStore;
+use picsharp_lib::file::parse_paths;
+use picsharp_lib::image_processor::audit::{audit_files, render_audit_report, AuditOptions};
+use picsharp_lib::image_processor::cache::OptimizedCache;
+use picsharp_lib::image_processor::common::{
+    CompressionOptions, CompressionOutputMode, CompressionResult, CompressionStatus, QualityMode,
+};
+use picsharp_lib::image_processor::compressors::{
+    compress_single_image, compression_options_from_default_dir, CompressionContext,
+    LOCAL_SUPPORTED_EXTS,
+};
+use picsharp_lib::image_processor::dedup::{find_duplicates, DedupOptions};
+use picsharp_lib::image_processor::estimate::estimate_compression;
+use picsharp_lib::report::{CompressionReport, ReportFormat};
+use picsharp_lib::scan::ScanOptions;
+use picsharp_lib::watcher::{FolderWatcher, WatchOptions};
+use rayon::prelude::*;
 use serde_json::json;
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::Command as ProcessCommand;
+use std::str::FromStr;
+use std::sync::Arc;
 
 fn main() {
     let app_version = env!("CARGO_PKG_VERSION");
@@ -44,6 +64,40 @@ fn main() {
                 .action(ArgAction::SetTrue)
                 .conflicts_with("gui"),
         )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .help("仅预估压缩效果，不写入文件")
+                .long_help("在内存中运行压缩器，输出每个文件及总计的预估压缩后大小，不会修改磁盘上的任何文件")
+                .action(ArgAction::SetTrue)
+                .requires("compress"),
+        )
+        .arg(
+            Arg::new("sample")
+                .long("sample")
+                .value_name("COUNT")
+                .help("预估时的抽样文件数")
+                .long_help("文件数量超过该值时只压缩均匀抽取的样本，其余文件按同格式样本的压缩率推算")
+                .value_parser(clap::value_parser!(usize))
+                .requires("dry-run"),
+        )
+        .arg(
+            Arg::new("report")
+                .long("report")
+                .value_name("FILE")
+                .help("压缩完成后导出报告")
+                .long_help("将每个文件的原始大小、压缩后大小、压缩率、耗时、状态和错误信息写入报告，格式根据文件扩展名（csv/json/html）确定")
+                .requires("compress")
+                .conflicts_with("dry-run"),
+        )
+        .arg(
+            Arg::new("report-format")
+                .long("report-format")
+                .value_name("FORMAT")
+                .help("报告格式，默认根据报告文件扩展名确定")
+                .value_parser(["csv", "json", "html"])
+                .requires("report"),
+        )
         .arg(
             Arg::new("paths")
                 .help("文件或文件夹路径")
@@ -83,11 +137,185 @@ fn main() {
                         .last(true),
                 ),
         )
+        // 监听文件夹子命令
+        .subcommand(
+            Command::new("watch")
+                .about("持续监听文件夹，自动压缩新增或修改的图片")
+                .long_about("以无界面方式持续监听文件夹，每处理一个文件输出一行JSON结果，收到 SIGINT/SIGTERM 后退出")
+                .display_order(2)
+                .arg(
+                    Arg::new("dir")
+                        .help("要监听的文件夹路径")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("level")
+                        .short('l')
+                        .long("level")
+                        .value_name("LEVEL")
+                        .help("压缩等级，1-6，数值越大压缩率越高")
+                        .value_parser(clap::value_parser!(u8).range(1..=6))
+                        .default_value("4"),
+                )
+                .arg(
+                    Arg::new("lossless")
+                        .long("lossless")
+                        .help("使用无损压缩")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("output-mode")
+                        .short('o')
+                        .long("output-mode")
+                        .value_name("MODE")
+                        .help("输出方式")
+                        .value_parser(["overwrite", "save_as_new_file", "save_to_new_folder"])
+                        .default_value("overwrite"),
+                )
+                .arg(
+                    Arg::new("suffix")
+                        .long("suffix")
+                        .value_name("SUFFIX")
+                        .help("输出方式为 save_as_new_file 时的文件名后缀")
+                        .default_value("_compressed"),
+                )
+                .arg(
+                    Arg::new("output-dir")
+                        .long("output-dir")
+                        .value_name("DIR")
+                        .help("输出方式为 save_to_new_folder 时的输出文件夹")
+                        .required_if_eq("output-mode", "save_to_new_folder"),
+                )
+                .arg(
+                    Arg::new("ignore")
+                        .short('i')
+                        .long("ignore")
+                        .value_name("PATTERN")
+                        .help("忽略匹配的文件或文件夹，可多次指定，默认忽略 .git 和 node_modules")
+                        .action(ArgAction::Append),
+                ),
+        )
+        // 恢复原图子命令
+        .subcommand(
+            Command::new("restore")
+                .about("从备份中恢复压缩前的原图")
+                .display_order(3)
+                .arg(
+                    Arg::new("job")
+                        .short('j')
+                        .long("job")
+                        .value_name("JOB_ID")
+                        .help("恢复某次压缩任务的全部原图")
+                        .conflicts_with_all(["ids", "list"]),
+                )
+                .arg(
+                    Arg::new("list")
+                        .short('l')
+                        .long("list")
+                        .help("列出所有备份")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("ids"),
+                )
+                .arg(
+                    Arg::new("ids")
+                        .help("备份ID")
+                        .long_help("指定要恢复的一个或多个备份ID")
+                        .num_args(1..),
+                ),
+        )
+        // 查找重复图片子命令
+        .subcommand(
+            Command::new("dedup")
+                .about("查找重复或相似的图片")
+                .long_about("按文件哈希查找内容完全相同的文件，按感知哈希查找视觉相似的图片，分组输出JSON结果并给出建议保留的文件")
+                .display_order(4)
+                .arg(
+                    Arg::new("paths")
+                        .help("图片文件或文件夹路径")
+                        .required(true)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("threshold")
+                        .short('t')
+                        .long("threshold")
+                        .value_name("DISTANCE")
+                        .help("相似判定阈值，感知哈希汉明距离 0-64，数值越大越宽松")
+                        .value_parser(clap::value_parser!(u32).range(0..=64))
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::new("exact-only")
+                        .long("exact-only")
+                        .help("只查找内容完全相同的文件")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        // 图片检查报告子命令
+        .subcommand(
+            Command::new("audit")
+                .about("检查尺寸过大或编码不合理的图片")
+                .long_about("找出超出显示需要的大图、可以转为 JPEG/WebP 的不透明 PNG 以及质量为 100 的 JPEG，为每个文件给出建议操作，输出JSON或HTML报告")
+                .display_order(5)
+                .arg(
+                    Arg::new("paths")
+                        .help("图片文件或文件夹路径")
+                        .required(true)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("max-dimension")
+                        .long("max-dimension")
+                        .value_name("PX")
+                        .help("图片宽或高超过该值时建议缩小")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .default_value("2560"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("报告格式")
+                        .value_parser(["json", "html"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .value_name("FILE")
+                        .help("报告输出文件，不指定时输出到标准输出"),
+                ),
+        )
         .get_matches();
 
+    // 处理监听文件夹
+    if let Some(watch_matches) = matches.subcommand_matches("watch") {
+        watch_dir(watch_matches);
+    }
+    // 处理恢复原图
+    else if let Some(restore_matches) = matches.subcommand_matches("restore") {
+        restore_backups(restore_matches);
+    }
+    // 处理查找重复图片
+    else if let Some(dedup_matches) = matches.subcommand_matches("dedup") {
+        find_duplicate_files(dedup_matches);
+    }
+    // 处理图片检查报告
+    else if let Some(audit_matches) = matches.subcommand_matches("audit") {
+        audit_images(audit_matches);
+    }
     // 处理GUI模式
-    if let Some(gui_matches) = matches.subcommand_matches("gui") {
-        let mut command = ProcessCommand::new("./PicSharp");
+    else if let Some(gui_matches) = matches.subcommand_matches("gui") {
+        let app_path = match resolve_app_binary() {
+            Some(app_path) => app_path,
+            None => {
+                output_error_json("未找到 PicSharp 应用，请确认已安装或设置 PICSHARP_APP_PATH");
+                std::process::exit(1);
+            }
+        };
+        // 应用已运行时，新进程会通过 single-instance 将参数转发给已运行的实例后退出
+        let mut command = ProcessCommand::new(&app_path);
 
         // 添加-c或--compress参数
         if gui_matches.get_flag("compress") {
@@ -105,9 +333,9 @@ fn main() {
                     }
                 }
 
-                // 添加文件路径参数
+                // 添加文件路径参数，使用绝对路径以便转发给已运行的实例
                 for path in paths_vec {
-                    command.arg(path);
+                    command.arg(absolute_path(path));
                 }
             }
         }
@@ -129,7 +357,7 @@ fn main() {
                         return;
                     }
 
-                    command.arg(path);
+                    command.arg(absolute_path(path));
                 }
             }
         }
@@ -138,7 +366,14 @@ fn main() {
             // 无需添加参数，直接启动主程序
         }
 
-        let _ = command.spawn().expect("failed to launch app");
+        if let Err(e) = command.spawn() {
+            output_error_json(&format!(
+                "启动应用失败: {} ({})",
+                e,
+                app_path.to_string_lossy()
+            ));
+            std::process::exit(1);
+        }
     }
     // 处理CLI模式（默认模式）
     else {
@@ -156,8 +391,13 @@ fn main() {
                     }
                 }
 
-                // 执行压缩逻辑
-                compress_files(&paths_vec);
+                if matches.get_flag("dry-run") {
+                    // 仅预估，不写入文件
+                    estimate_files(&paths_vec, matches.get_one::<usize>("sample").copied());
+                } else {
+                    // 执行压缩逻辑
+                    compress_files(&paths_vec, &matches);
+                }
             } else {
                 output_error_json("请指定至少一个文件或文件夹路径");
             }
@@ -191,6 +431,63 @@ fn dir_exists(path: &str) -> bool {
     path.exists() && path.is_dir()
 }
 
+#[cfg(target_os = "windows")]
+const APP_BINARY_NAME: &str = "PicSharp.exe";
+#[cfg(not(target_os = "windows"))]
+const APP_BINARY_NAME: &str = "PicSharp";
+
+// 各平台默认安装位置中的应用可执行文件
+fn installed_app_candidates() -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    #[cfg(target_os = "macos")]
+    {
+        let bundle = Path::new("PicSharp.app/Contents/MacOS").join(APP_BINARY_NAME);
+        candidates.push(Path::new("/Applications").join(&bundle));
+        if let Some(home) = dirs::home_dir() {
+            candidates.push(home.join("Applications").join(&bundle));
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        for var in ["LOCALAPPDATA", "ProgramFiles", "ProgramFiles(x86)"] {
+            if let Some(dir) = std::env::var_os(var) {
+                candidates.push(PathBuf::from(dir).join("PicSharp").join(APP_BINARY_NAME));
+            }
+        }
+    }
+    #[cfg(target_os = "linux")]
+    {
+        for dir in ["/usr/bin", "/usr/local/bin", "/opt/PicSharp"] {
+            candidates.push(Path::new(dir).join(APP_BINARY_NAME));
+            candidates.push(Path::new(dir).join("pic-sharp"));
+        }
+    }
+    candidates
+}
+
+// 查找应用可执行文件：PICSHARP_APP_PATH > CLI 所在目录 > 各平台安装位置
+fn resolve_app_binary() -> Option<PathBuf> {
+    if let Some(app_path) = std::env::var_os("PICSHARP_APP_PATH") {
+        return Some(PathBuf::from(app_path));
+    }
+
+    let mut candidates = Vec::new();
+    if let Ok(exe) = std::env::current_exe() {
+        // CLI 可能通过软链接安装到 PATH 中，解析到真实位置
+        let exe = dunce::canonicalize(&exe).unwrap_or(exe);
+        if let Some(dir) = exe.parent() {
+            candidates.push(dir.join(APP_BINARY_NAME));
+        }
+    }
+    candidates.extend(installed_app_candidates());
+
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+fn absolute_path(path: &str) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
 // 输出JSON格式错误信息
 fn output_error_json(message: &str) {
     let error_json = json!({
@@ -202,19 +499,359 @@ fn output_error_json(message: &str) {
 }
 
 // 压缩文件的CLI逻辑
-fn compress_files(paths: &[&String]) {
-    // 模拟压缩过程，实际实现应该调用实际的压缩功能
-    println!("开始压缩...");
+fn compress_files(paths: &[&String], matches: &ArgMatches) {
+    // 报告格式优先使用 --report-format，否则根据扩展名确定
+    let report = match matches.get_one::<String>("report") {
+        Some(report_path) => {
+            let format = matches
+                .get_one::<String>("report-format")
+                .and_then(|format| ReportFormat::from_str(format).ok())
+                .or_else(|| ReportFormat::from_path(Path::new(report_path)));
+            match format {
+                Some(format) => Some((absolute_path(report_path), format)),
+                None => {
+                    output_error_json(&format!(
+                        "无法识别报告格式，请使用 .csv/.json/.html 扩展名或 --report-format: {}",
+                        report_path
+                    ));
+                    return;
+                }
+            }
+        }
+        None => None,
+    };
 
-    // 在这里添加实际的文件压缩逻辑
-    // ...
+    let files = parse_paths(
+        paths.iter().map(|path| path.to_string()).collect(),
+        LOCAL_SUPPORTED_EXTS
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+        &ScanOptions::default(),
+    );
+    // 使用 GUI 保存的设置，与应用内压缩结果一致
+    let options = compression_options_from_default_dir();
+    let context = CompressionContext::from_default_dir(None);
+
+    let results: Vec<CompressionResult> = files
+        .par_iter()
+        .map(|file| {
+            compress_single_image(&file.path, &context, &options).unwrap_or_else(|e| {
+                CompressionResult {
+                    input_path: file.path.to_string_lossy().to_string(),
+                    status: CompressionStatus::Failed,
+                    original_bytes_size: file.bytes_size,
+                    output_path: "".to_string(),
+                    output_path_converted: "".to_string(),
+                    compressed_bytes_size: 0,
+                    compressed_disk_size: 0,
+                    cost_time: 0,
+                    compress_rate: 0.0,
+                    error_message: Some(e),
+                    original_temp_path: "".to_string(),
+                    job_id: context.job_id.clone(),
+                    backup_id: None,
+                }
+            })
+        })
+        .collect();
+    context.record_results(&results);
+    context.finish();
+
+    let has_failed = results
+        .iter()
+        .any(|r| matches!(r.status, CompressionStatus::Failed));
+    let mut result_json = json!({
+        "status": if has_failed { "error" } else { "success" },
+        "job_id": context.job_id,
+        "results": results,
+    });
+
+    if let Some((report_path, format)) = report {
+        let report = CompressionReport::from_results(&context.job_id, &results);
+        match report.write(format, &report_path) {
+            Ok(_) => {
+                result_json["report_path"] = json!(report_path);
+                result_json["totals"] = json!(report.totals);
+            }
+            Err(e) => {
+                output_error_json(&format!("写入报告失败: {}", e));
+                return;
+            }
+        }
+    }
+
+    println!("{}", serde_json::to_string_pretty(&result_json).unwrap());
+}
+
+// 预估压缩效果的CLI逻辑
+fn estimate_files(paths: &[&String], sample_size: Option<usize>) {
+    let files = parse_paths(
+        paths.iter().map(|path| path.to_string()).collect(),
+        LOCAL_SUPPORTED_EXTS
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+        &ScanOptions::default(),
+    );
+    let file_paths: Vec<PathBuf> = files.into_iter().map(|file| file.path).collect();
+
+    // 使用 GUI 保存的设置与压缩缓存，预估结果与实际压缩一致
+    let cache = OptimizedCache::from_default_dir();
+    let result = estimate_compression(
+        &file_paths,
+        &compression_options_from_default_dir(),
+        cache.as_deref(),
+        sample_size,
+    );
+
+    let result_json = json!({
+        "status": "success",
+        "dry_run": true,
+        "estimate": result,
+    });
+
+    println!("{}", serde_json::to_string_pretty(&result_json).unwrap());
+}
+
+// 查找重复图片的CLI逻辑
+fn find_duplicate_files(matches: &ArgMatches) {
+    let paths: Vec<String> = matches
+        .get_many::<String>("paths")
+        .map(|paths| paths.cloned().collect())
+        .unwrap_or_default();
+    let files = parse_paths(
+        paths,
+        LOCAL_SUPPORTED_EXTS
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+        &ScanOptions::default(),
+    );
+    let options = DedupOptions {
+        similar: !matches.get_flag("exact-only"),
+        similar_threshold: *matches.get_one::<u32>("threshold").unwrap(),
+    };
 
-    // 输出成功的JSON结果
     let result_json = json!({
         "status": "success",
-        "compressed_files": paths,
-        "message": "压缩完成"
+        "result": find_duplicates(&files, &options),
     });
 
     println!("{}", serde_json::to_string_pretty(&result_json).unwrap());
 }
+
+// 图片检查报告的CLI逻辑
+fn audit_images(matches: &ArgMatches) {
+    let paths: Vec<String> = matches
+        .get_many::<String>("paths")
+        .map(|paths| paths.cloned().collect())
+        .unwrap_or_default();
+    let files = parse_paths(
+        paths,
+        LOCAL_SUPPORTED_EXTS
+            .iter()
+            .map(|ext| ext.to_string())
+            .collect(),
+        &ScanOptions::default(),
+    );
+    let options = AuditOptions {
+        max_dimension: *matches.get_one::<u32>("max-dimension").unwrap(),
+        ..Default::default()
+    };
+    let report = audit_files(&files, &options);
+    let format = ReportFormat::from_str(matches.get_one::<String>("format").unwrap())
+        .unwrap_or(ReportFormat::Json);
+
+    let rendered = match render_audit_report(&report, format) {
+        Ok(rendered) => rendered,
+        Err(e) => {
+            output_error_json(&e);
+            return;
+        }
+    };
+
+    match matches.get_one::<String>("output") {
+        Some(output) => match fs::write(output, rendered) {
+            Ok(_) => {
+                let result_json = json!({
+                    "status": "success",
+                    "report_path": absolute_path(output),
+                    "total_count": report.total_count,
+                    "flagged_count": report.flagged_count,
+                });
+                println!("{}", serde_json::to_string_pretty(&result_json).unwrap());
+            }
+            Err(e) => output_error_json(&e.to_string()),
+        },
+        None if format == ReportFormat::Json => {
+            let result_json = json!({
+                "status": "success",
+                "report": report,
+            });
+            println!("{}", serde_json::to_string_pretty(&result_json).unwrap());
+        }
+        None => println!("{}", rendered),
+    }
+}
+
+// 根据命令行参数生成压缩选项
+fn compression_options_from_matches(matches: &ArgMatches) -> CompressionOptions {
+    let output_mode = matches
+        .get_one::<String>("output-mode")
+        .and_then(|mode| CompressionOutputMode::from_str(mode).ok())
+        .unwrap_or(CompressionOutputMode::Overwrite);
+
+    CompressionOptions {
+        output_mode,
+        output_mode_save_as_file_suffix: matches
+            .get_one::<String>("suffix")
+            .cloned()
+            .unwrap_or_default(),
+        output_mode_save_to_folder: matches
+            .get_one::<String>("output-dir")
+            .cloned()
+            .unwrap_or_default(),
+        quality_level: matches.get_one::<u8>("level").copied().unwrap_or(4),
+        quality_mode: if matches.get_flag("lossless") {
+            QualityMode::Lossless
+        } else {
+            QualityMode::Lossy
+        },
+        ..CompressionOptions::default()
+    }
+}
+
+// 等待 SIGINT，Unix 下同时等待 SIGTERM
+async fn wait_for_shutdown() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+// 监听文件夹的CLI逻辑，每处理一个文件输出一行JSON
+fn watch_dir(matches: &ArgMatches) {
+    let dir = matches
+        .get_one::<String>("dir")
+        .cloned()
+        .unwrap_or_default();
+    if !dir_exists(&dir) {
+        output_error_json(&format!("路径不存在、不是文件夹或无法访问: {}", dir));
+        std::process::exit(1);
+    }
+
+    let options = Arc::new(compression_options_from_matches(matches));
+    let mut watch_options = WatchOptions {
+        include_modified: true,
+        ..WatchOptions::default()
+    };
+    if let Some(ignores) = matches.get_many::<String>("ignore") {
+        watch_options.ignores = ignores.cloned().collect();
+    }
+
+    let watcher = FolderWatcher::start(Path::new(&dir), watch_options, move |paths| {
+        let context = CompressionContext::from_default_dir(None);
+        paths.par_iter().for_each(|path| {
+            let line = match compress_single_image(path, &context, &options) {
+                Ok(result) => serde_json::to_string(&result),
+                Err(e) => serde_json::to_string(&json!({
+                    "input_path": path,
+                    "status": "Failed",
+                    "error_message": e,
+                    "job_id": context.job_id,
+                })),
+            };
+            if let Ok(line) = line {
+                let mut stdout = std::io::stdout().lock();
+                let _ = writeln!(stdout, "{}", line);
+                let _ = stdout.flush();
+            }
+        });
+        context.finish();
+    });
+    let watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            output_error_json(&format!("监听文件夹失败: {}", e));
+            std::process::exit(1);
+        }
+    };
+    eprintln!("Watching {}", watcher.root().display());
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            output_error_json(&e.to_string());
+            std::process::exit(1);
+        }
+    };
+    runtime.block_on(wait_for_shutdown());
+
+    // 等待正在处理的文件完成后退出
+    watcher.stop();
+    eprintln!("Stopped watching {}", dir);
+}
+
+// 恢复原图的CLI逻辑
+fn restore_backups(matches: &ArgMatches) {
+    let store = match BackupStore::from_default_dir() {
+        Ok(store) => store,
+        Err(e) => {
+            output_error_json(&e.to_string());
+            return;
+        }
+    };
+
+    if matches.get_flag("list") {
+        match store.entries() {
+            Ok(entries) => {
+                let result_json = json!({
+                    "status": "success",
+                    "backups": entries,
+                });
+                println!("{}", serde_json::to_string_pretty(&result_json).unwrap());
+            }
+            Err(e) => output_error_json(&e.to_string()),
+        }
+        return;
+    }
+
+    let results = if let Some(job_id) = matches.get_one::<String>("job") {
+        store.restore_job(job_id)
+    } else if let Some(ids) = matches.get_many::<String>("ids") {
+        store.restore(&ids.cloned().collect::<Vec<_>>())
+    } else {
+        output_error_json("请指定备份ID或 --job 任务ID");
+        return;
+    };
+
+    match results {
+        Ok(results) => {
+            let result_json = json!({
+                "status": if results.iter().all(|r| r.success) { "success" } else { "error" },
+                "results": results,
+            });
+            println!("{}", serde_json::to_string_pretty(&result_json).unwrap());
+        }
+        Err(e) => output_error_json(&e.to_string()),
+    }
+}
@@ -0,0 +1,550 @@
+use crate::file::{get_file_hash, get_file_name};
+use crate::APP_IDENTIFIER;
+use log::{error, info};
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+
+pub const TEMP_DIR_NAME: &str = "picsharp_temp";
+const BACKUP_DIR_NAME: &str = "backups";
+const MANIFEST_FILE_NAME: &str = "manifest.json";
+const LOCK_FILE_NAME: &str = "manifest.lock";
+// manifest 中没有记录的文件超过该时间才会被清理，避免删除其他进程正在写入的备份
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+// 并行压缩时串行化 manifest 的读写
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// 持有期间独占 manifest，GUI 与 CLI 等多个进程之间同样互斥
+struct ManifestLock {
+    _file: File,
+    _guard: MutexGuard<'static, ()>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to parse backup manifest: {0}")]
+    Manifest(#[from] serde_json::Error),
+    #[error("Backup not found: {0}")]
+    NotFound(String),
+    #[error("Backup file is corrupted: {0}")]
+    HashMismatch(String),
+    #[error("Cannot resolve backup directory: {0}")]
+    Dir(String),
+}
+
+type Result<T> = std::result::Result<T, BackupError>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub id: String,
+    pub job_id: String,
+    pub original_path: String,
+    pub backup_path: String,
+    // 原文件的 sha256
+    pub hash: String,
+    pub bytes_size: u64,
+    // 备份时间（unix 秒）
+    pub created_at: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    entries: Vec<BackupEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestoreResult {
+    pub id: String,
+    pub original_path: String,
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    // 备份最长保留天数
+    pub max_age_days: Option<u64>,
+    // 备份目录最大占用（GB）
+    pub max_size_gb: Option<f64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(7),
+            max_size_gb: Some(1.0),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    pub fn from_store<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let default_policy = Self::default();
+        let Ok(store) = app.store("settings.json") else {
+            return default_policy;
+        };
+        // 设置为 0 表示不限制
+        let max_age_days = match store
+            .get("compression_backup_retention_days")
+            .and_then(|v| v.as_u64())
+        {
+            Some(0) => None,
+            Some(days) => Some(days),
+            None => default_policy.max_age_days,
+        };
+        let max_size_gb = match store
+            .get("compression_backup_max_size_gb")
+            .and_then(|v| v.as_f64())
+        {
+            Some(size) if size <= 0.0 => None,
+            Some(size) => Some(size),
+            None => default_policy.max_size_gb,
+        };
+        Self {
+            max_age_days,
+            max_size_gb,
+        }
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 原图备份存储，位于 `app_cache_dir/picsharp_temp/backups`，
+/// 通过 manifest.json 记录原路径、备份路径、哈希和时间。
+#[derive(Debug, Clone)]
+pub struct BackupStore {
+    dir: PathBuf,
+}
+
+impl BackupStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn from_temp_dir(temp_dir: &Path) -> Self {
+        Self::new(temp_dir.join(BACKUP_DIR_NAME))
+    }
+
+    pub fn from_app<R: Runtime>(app: &AppHandle<R>) -> Result<Self> {
+        let cache_dir = app
+            .path()
+            .app_cache_dir()
+            .map_err(|e| BackupError::Dir(e.to_string()))?;
+        Ok(Self::from_temp_dir(&cache_dir.join(TEMP_DIR_NAME)))
+    }
+
+    /// 不依赖 AppHandle 定位应用的备份目录，供 CLI 使用
+    pub fn from_default_dir() -> Result<Self> {
+        let cache_dir = dirs::cache_dir()
+            .ok_or_else(|| BackupError::Dir("cache dir not available".to_string()))?;
+        Ok(Self::from_temp_dir(
+            &cache_dir.join(APP_IDENTIFIER).join(TEMP_DIR_NAME),
+        ))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.dir.join(MANIFEST_FILE_NAME)
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        self.dir.join(LOCK_FILE_NAME)
+    }
+
+    // manifest.json 通过 rename 替换，锁加在单独的文件上
+    fn lock(&self) -> Result<ManifestLock> {
+        let guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.dir)?;
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.lock_path())?;
+        file.lock()?;
+        Ok(ManifestLock {
+            _file: file,
+            _guard: guard,
+        })
+    }
+
+    fn load_manifest(&self) -> Result<Manifest> {
+        let path = self.manifest_path();
+        if !path.exists() {
+            return Ok(Manifest::default());
+        }
+        let data = fs::read(path)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    fn save_manifest(&self, manifest: &Manifest) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.manifest_path();
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(manifest)?)?;
+        fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    fn update_manifest<T>(&self, f: impl FnOnce(&mut Manifest) -> Result<T>) -> Result<T> {
+        let _lock = self.lock()?;
+        let mut manifest = self.load_manifest()?;
+        let result = f(&mut manifest)?;
+        self.save_manifest(&manifest)?;
+        Ok(result)
+    }
+
+    /// 备份原文件并记录到 manifest，复制与写入 manifest 期间持有锁，避免备份被并发的清理删除
+    pub fn backup(&self, original_path: &Path, job_id: &str) -> Result<BackupEntry> {
+        self.update_manifest(|manifest| {
+            let id = nanoid!();
+            let backup_path = self
+                .dir
+                .join(format!("{}_{}", id, get_file_name(original_path)));
+            let entry = Self::copy_to_backup(original_path, &backup_path, id, job_id);
+            match entry {
+                Ok(entry) => {
+                    manifest.entries.push(entry.clone());
+                    Ok(entry)
+                }
+                Err(e) => {
+                    let _ = fs::remove_file(&backup_path);
+                    Err(e)
+                }
+            }
+        })
+    }
+
+    /// 备份即将被覆盖的文件，文件不存在时无需备份
+    pub fn backup_replaced(&self, path: &Path, job_id: &str) -> Result<Option<BackupEntry>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        self.backup(path, job_id).map(Some)
+    }
+
+    fn copy_to_backup(
+        original_path: &Path,
+        backup_path: &Path,
+        id: String,
+        job_id: &str,
+    ) -> Result<BackupEntry> {
+        let bytes_size = fs::copy(original_path, backup_path)?;
+        // 部分平台复制时保留原文件的修改时间，清理孤立文件按修改时间计算宽限期
+        File::options()
+            .write(true)
+            .open(backup_path)?
+            .set_modified(SystemTime::now())?;
+        Ok(BackupEntry {
+            id,
+            job_id: job_id.to_string(),
+            original_path: dunce::canonicalize(original_path)
+                .unwrap_or_else(|_| original_path.to_path_buf())
+                .to_string_lossy()
+                .to_string(),
+            backup_path: backup_path.to_string_lossy().to_string(),
+            hash: get_file_hash(backup_path)?,
+            bytes_size,
+            created_at: now_secs(),
+        })
+    }
+
+    pub fn entries(&self) -> Result<Vec<BackupEntry>> {
+        let _lock = self.lock()?;
+        Ok(self.load_manifest()?.entries)
+    }
+
+    fn restore_entry(entry: &BackupEntry) -> Result<()> {
+        let backup_path = Path::new(&entry.backup_path);
+        if !backup_path.exists() {
+            return Err(BackupError::NotFound(entry.backup_path.clone()));
+        }
+        if get_file_hash(backup_path)? != entry.hash {
+            return Err(BackupError::HashMismatch(entry.backup_path.clone()));
+        }
+        let original_path = Path::new(&entry.original_path);
+        if let Some(parent) = original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(backup_path, original_path)?;
+        Ok(())
+    }
+
+    fn restore_entries(entries: Vec<&BackupEntry>) -> Vec<RestoreResult> {
+        entries
+            .into_iter()
+            .map(|entry| match Self::restore_entry(entry) {
+                Ok(()) => RestoreResult {
+                    id: entry.id.clone(),
+                    original_path: entry.original_path.clone(),
+                    success: true,
+                    error_message: None,
+                },
+                Err(e) => {
+                    error!("[restore] -> Failed to restore <{}>: {}", entry.id, e);
+                    RestoreResult {
+                        id: entry.id.clone(),
+                        original_path: entry.original_path.clone(),
+                        success: false,
+                        error_message: Some(e.to_string()),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// 按备份 id 恢复原文件
+    pub fn restore(&self, ids: &[String]) -> Result<Vec<RestoreResult>> {
+        let entries = self.entries()?;
+        let mut results = Vec::with_capacity(ids.len());
+        for id in ids {
+            match entries.iter().find(|entry| &entry.id == id) {
+                Some(entry) => results.extend(Self::restore_entries(vec![entry])),
+                None => results.push(RestoreResult {
+                    id: id.clone(),
+                    original_path: String::new(),
+                    success: false,
+                    error_message: Some(BackupError::NotFound(id.clone()).to_string()),
+                }),
+            }
+        }
+        Ok(results)
+    }
+
+    /// 恢复某次任务中备份的全部原文件
+    pub fn restore_job(&self, job_id: &str) -> Result<Vec<RestoreResult>> {
+        let entries = self.entries()?;
+        let job_entries: Vec<&BackupEntry> = entries
+            .iter()
+            .filter(|entry| entry.job_id == job_id)
+            .collect();
+        if job_entries.is_empty() {
+            return Err(BackupError::NotFound(job_id.to_string()));
+        }
+        Ok(Self::restore_entries(job_entries))
+    }
+
+    /// 按保留策略清理过期备份，返回删除的条目数
+    pub fn prune(&self, policy: &RetentionPolicy) -> Result<usize> {
+        self.update_manifest(|manifest| {
+            let now = now_secs();
+            let before = manifest.entries.len();
+
+            manifest.entries.retain(|entry| {
+                let expired = policy
+                    .max_age_days
+                    .map(|days| now.saturating_sub(entry.created_at) > days * 24 * 60 * 60)
+                    .unwrap_or(false);
+                let missing = !Path::new(&entry.backup_path).exists();
+                if expired && !missing {
+                    let _ = fs::remove_file(&entry.backup_path);
+                }
+                !expired && !missing
+            });
+
+            if let Some(max_size_gb) = policy.max_size_gb {
+                let max_bytes = (max_size_gb * 1024.0 * 1024.0 * 1024.0) as u64;
+                let mut total: u64 = manifest.entries.iter().map(|e| e.bytes_size).sum();
+                // 从最旧的备份开始删除，直到低于上限
+                manifest.entries.sort_by_key(|entry| entry.created_at);
+                while total > max_bytes && !manifest.entries.is_empty() {
+                    let entry = manifest.entries.remove(0);
+                    let _ = fs::remove_file(&entry.backup_path);
+                    total = total.saturating_sub(entry.bytes_size);
+                }
+            }
+
+            // 删除 manifest 中没有记录且超过宽限期的文件
+            if let Ok(read_dir) = fs::read_dir(&self.dir) {
+                for dir_entry in read_dir.filter_map(|e| e.ok()) {
+                    let path = dir_entry.path();
+                    if path == self.manifest_path() || path == self.lock_path() || !path.is_file() {
+                        continue;
+                    }
+                    let recent = dir_entry
+                        .metadata()
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_none_or(|age| age < ORPHAN_GRACE_PERIOD);
+                    if recent {
+                        continue;
+                    }
+                    let path_str = path.to_string_lossy();
+                    if !manifest
+                        .entries
+                        .iter()
+                        .any(|entry| entry.backup_path == path_str)
+                    {
+                        let _ = fs::remove_file(&path);
+                    }
+                }
+            }
+
+            let removed = before - manifest.entries.len();
+            info!("[prune] -> Removed {} expired backups", removed);
+            Ok(removed)
+        })
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_restore_original<R: Runtime>(
+    app: AppHandle<R>,
+    ids: Vec<String>,
+) -> std::result::Result<Vec<RestoreResult>, String> {
+    let store = BackupStore::from_app(&app).map_err(|e| e.to_string())?;
+    store.restore(&ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn ipc_restore_job<R: Runtime>(
+    app: AppHandle<R>,
+    job_id: String,
+) -> std::result::Result<Vec<RestoreResult>, String> {
+    let store = BackupStore::from_app(&app).map_err(|e| e.to_string())?;
+    store.restore_job(&job_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_file;
+
+    fn temp_store() -> BackupStore {
+        BackupStore::new(std::env::temp_dir().join(format!("picsharp-test-{}", nanoid!())))
+    }
+
+    #[test]
+    fn backs_up_and_restores_original() {
+        let store = temp_store();
+        let original = temp_file("png", b"original");
+        let entry = store.backup(&original, "job").unwrap();
+        assert_eq!(entry.bytes_size, 8);
+        assert_eq!(store.entries().unwrap().len(), 1);
+
+        fs::write(&original, b"compressed").unwrap();
+        let results = store.restore(std::slice::from_ref(&entry.id)).unwrap();
+        assert!(results[0].success);
+        assert_eq!(fs::read(&original).unwrap(), b"original");
+
+        fs::write(&original, b"compressed").unwrap();
+        let results = store.restore_job("job").unwrap();
+        assert!(results[0].success);
+        assert_eq!(fs::read(&original).unwrap(), b"original");
+
+        let _ = fs::remove_file(original);
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn skips_backup_of_missing_output() {
+        let store = temp_store();
+        let missing = store.dir().join("missing.png");
+        assert!(store.backup_replaced(&missing, "job").unwrap().is_none());
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn refuses_to_restore_corrupted_backup() {
+        let store = temp_store();
+        let original = temp_file("png", b"original");
+        let entry = store.backup(&original, "job").unwrap();
+        fs::write(&entry.backup_path, b"tampered").unwrap();
+        fs::write(&original, b"compressed").unwrap();
+
+        let results = store.restore(std::slice::from_ref(&entry.id)).unwrap();
+        assert!(!results[0].success);
+        assert_eq!(
+            results[0].error_message.as_deref(),
+            Some(
+                BackupError::HashMismatch(entry.backup_path.clone())
+                    .to_string()
+                    .as_str()
+            )
+        );
+        assert_eq!(fs::read(&original).unwrap(), b"compressed");
+
+        let missing = store.restore(&["unknown".to_string()]).unwrap();
+        assert!(!missing[0].success);
+
+        let _ = fs::remove_file(original);
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn prunes_expired_and_oversized_backups() {
+        let store = temp_store();
+        let original = temp_file("png", &[0u8; 1024]);
+        let expired = store.backup(&original, "old").unwrap();
+        let oldest = store.backup(&original, "job").unwrap();
+        let newest = store.backup(&original, "job").unwrap();
+        store
+            .update_manifest(|manifest| {
+                let now = now_secs();
+                for entry in manifest.entries.iter_mut() {
+                    entry.created_at = match entry.id {
+                        ref id if *id == expired.id => now - 10 * 24 * 60 * 60,
+                        ref id if *id == oldest.id => now - 2,
+                        _ => now - 1,
+                    };
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        // 7 天过期，且最多保留 1.5 个备份的大小
+        let policy = RetentionPolicy {
+            max_age_days: Some(7),
+            max_size_gb: Some(1536.0 / 1024.0 / 1024.0 / 1024.0),
+        };
+        assert_eq!(store.prune(&policy).unwrap(), 2);
+        let ids: Vec<String> = store.entries().unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![newest.id.clone()]);
+        assert!(!Path::new(&expired.backup_path).exists());
+        assert!(!Path::new(&oldest.backup_path).exists());
+        assert!(Path::new(&newest.backup_path).exists());
+
+        let _ = fs::remove_file(original);
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn prunes_only_old_orphan_files() {
+        let store = temp_store();
+        fs::create_dir_all(store.dir()).unwrap();
+        let recent = store.dir().join("recent_orphan.png");
+        let old = store.dir().join("old_orphan.png");
+        fs::write(&recent, b"recent").unwrap();
+        fs::write(&old, b"old").unwrap();
+        File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - ORPHAN_GRACE_PERIOD * 2)
+            .unwrap();
+
+        store.prune(&RetentionPolicy::default()).unwrap();
+        assert!(recent.exists());
+        assert!(!old.exists());
+        assert!(store.lock_path().exists());
+
+        let _ = fs::remove_dir_all(store.dir());
+    }
+}
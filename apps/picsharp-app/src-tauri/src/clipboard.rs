@@ -1,7 +1,8 @@
+use crate::file::detect_image_format;
+use crate::http::HttpConfig;
 use arboard::Clipboard;
 use image::{ImageBuffer, ImageFormat, ImageReader, Rgba};
 use log::{error, info};
-use reqwest;
 use std::fs;
 use std::io::{Cursor, Write};
 use std::path::{Path, PathBuf};
@@ -9,15 +10,6 @@ use tauri::ipc::Response;
 use tauri::{AppHandle, Runtime};
 use url::Url;
 
-fn detect_image_format(image_data: &[u8]) -> Option<ImageFormat> {
-    let cursor = Cursor::new(image_data);
-    let reader = ImageReader::new(cursor);
-    if let Ok(reader_with_format) = reader.with_guessed_format() {
-        return reader_with_format.format();
-    }
-    None
-}
-
 fn format_to_extension(format: ImageFormat) -> &'static str {
     match format {
         ImageFormat::Png => "png",
@@ -112,8 +104,11 @@ fn save_image_data_to_temp(image_data: &[u8]) -> Result<String, Box<dyn std::err
     Ok(temp_path.to_string_lossy().to_string())
 }
 
-async fn download_image_from_url(url: &Url) -> Result<String, Box<dyn std::error::Error>> {
-    let response = reqwest::get(url.as_str()).await?;
+async fn download_image_from_url(
+    url: &Url,
+    config: &HttpConfig,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let response = config.client()?.get(url.as_str()).send().await?;
 
     if !response.status().is_success() {
         return Err(format!("Failed to download image from URL: {}", response.status()).into());
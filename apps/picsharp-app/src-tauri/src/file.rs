@@ -1,15 +1,25 @@
+use crate::http::HttpConfig;
+use crate::image_processor::info::{read_image_info, ImageInfo};
+use crate::scan::{normalize_exts, walk_files, ScanOptions};
 use arboard::Clipboard;
 use filesize::PathExt;
+use image::{ImageFormat, ImageReader};
+use log::warn;
 use nanoid::nanoid;
 use rayon::prelude::*;
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 use std::error::Error;
 use std::ffi::OsStr;
-use std::fs::{metadata, Metadata};
-use std::io;
+use std::fs::{self, metadata, File, Metadata};
+use std::io::{self, Cursor, Read};
 use std::path::{Path, PathBuf};
-use tauri::ipc::Response;
-use walkdir::{DirEntry, WalkDir};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, Response};
+use tauri::{AppHandle, Runtime};
+use walkdir::DirEntry;
 
 pub fn convert_file_src(orig_path: &str) -> Result<String, io::Error> {
     let base = "asset://localhost/";
@@ -36,6 +46,17 @@ pub fn get_file_bytes_size(path: &str, metadata: Option<&Metadata>) -> Result<u6
     }
 }
 
+pub fn get_bytes_hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+pub fn get_file_hash(path: &Path) -> Result<String, io::Error> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
 pub fn get_file_name(path: &Path) -> String {
     path.file_name()
         .and_then(|n| n.to_str())
@@ -51,7 +72,7 @@ pub fn get_file_extension(path: &Path) -> String {
 }
 
 pub fn get_file_mime_type(path: &Path) -> Option<String> {
-    match path.extension().and_then(OsStr::to_str).unwrap_or_default() {
+    match get_file_extension(path).as_str() {
         "jpg" | "jpeg" => Some("image/jpeg".to_string()),
         "png" => Some("image/png".to_string()),
         "webp" => Some("image/webp".to_string()),
@@ -63,6 +84,47 @@ pub fn get_file_mime_type(path: &Path) -> Option<String> {
     }
 }
 
+pub fn detect_image_format(image_data: &[u8]) -> Option<ImageFormat> {
+    let cursor = Cursor::new(image_data);
+    let reader = ImageReader::new(cursor);
+    if let Ok(reader_with_format) = reader.with_guessed_format() {
+        return reader_with_format.format();
+    }
+    None
+}
+
+// 识别格式所需的文件头长度
+const FORMAT_SNIFF_LEN: u64 = 64;
+
+/// 读取文件头识别真实格式，返回对应的扩展名，无法识别时返回 None
+pub fn sniff_file_extension(path: &Path) -> Option<String> {
+    let mut header = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(FORMAT_SNIFF_LEN)
+        .read_to_end(&mut header)
+        .ok()?;
+    detect_image_format(&header)
+        .and_then(|format| format.extensions_str().first())
+        .map(|ext| ext.to_string())
+}
+
+/// 扩展名声明的格式与文件头识别的格式是否不一致
+pub fn is_ext_mismatch(ext: &str, real_ext: &str) -> bool {
+    !real_ext.is_empty()
+        && ImageFormat::from_extension(ext) != ImageFormat::from_extension(real_ext)
+}
+
+/// 返回文件头识别的真实格式（无法识别时为空），真实格式不在允许的扩展名中时返回 None，
+/// 例如伪装成 .png 的 bmp 文件。`parse_paths` 与 `count_valid_files` 共用，保证两者结果一致
+pub(crate) fn sniff_valid_format(path: &Path, valid_exts: &HashSet<String>) -> Option<String> {
+    let real_ext = sniff_file_extension(path).unwrap_or_default();
+    valid_exts
+        .iter()
+        .any(|valid_ext| !is_ext_mismatch(valid_ext, &real_ext))
+        .then_some(real_ext)
+}
+
 pub fn get_file_parent_dir(path: &Path) -> PathBuf {
     path.parent().map(|p| p.to_path_buf()).unwrap_or_default()
 }
@@ -77,18 +139,32 @@ pub struct FileInfo {
     pub path: PathBuf,
     // 文件所在目录
     pub base_dir: PathBuf,
+    // 拖入路径的所在目录，输出到新文件夹时据此保留相对目录结构
+    pub root_dir: PathBuf,
     // 文件字节数
     pub bytes_size: u64,
     // 文件磁盘大小
     pub disk_size: u64,
     // 文件扩展名
     pub ext: String,
-    // 文件mime类型
+    // 根据文件头识别的真实格式扩展名，无法识别时为空
+    pub real_ext: String,
+    // 扩展名与真实格式不一致
+    pub ext_mismatch: bool,
+    // 文件mime类型，优先使用真实格式
     pub mime_type: String,
+    // 图片头信息，仅在 `ScanOptions::with_image_info` 开启时读取
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_info: Option<ImageInfo>,
 }
 
 impl FileInfo {
-    fn from_entry(entry: &DirEntry, valid_exts: Vec<String>) -> Option<Self> {
+    fn from_entry(
+        entry: &DirEntry,
+        root_dir: &Path,
+        valid_exts: &HashSet<String>,
+        options: &ScanOptions,
+    ) -> Option<Self> {
         let path = entry.path();
         let md = metadata(path).ok()?;
 
@@ -99,9 +175,20 @@ impl FileInfo {
         let name = get_file_name(path);
         let ext = get_file_extension(path);
 
-        if !valid_exts.iter().any(|valid_ext| valid_ext == &ext) {
-            return None;
+        let real_ext = sniff_valid_format(path, valid_exts)?;
+        let ext_mismatch = is_ext_mismatch(&ext, &real_ext);
+        if ext_mismatch {
+            warn!(
+                "[FileInfo] -> <{}> is actually a {} file",
+                path.to_string_lossy(),
+                real_ext
+            );
         }
+        let mime_type = if real_ext.is_empty() {
+            get_file_mime_type(path)
+        } else {
+            get_file_mime_type(Path::new(&format!("file.{}", real_ext)))
+        };
 
         let base_dir = get_file_parent_dir(path);
 
@@ -110,59 +197,187 @@ impl FileInfo {
             name,
             path: path.to_path_buf(),
             base_dir,
+            root_dir: root_dir.to_path_buf(),
             bytes_size: get_file_bytes_size(path.to_str().unwrap_or_default(), Some(&md))
                 .unwrap_or(0),
             disk_size: get_file_disk_size(path.to_str().unwrap_or_default(), Some(&md))
                 .unwrap_or(0),
+            ext_mismatch,
             ext,
-            mime_type: get_file_mime_type(path).unwrap_or_default(),
+            real_ext,
+            mime_type: mime_type.unwrap_or_default(),
+            image_info: if options.with_image_info {
+                read_image_info(path)
+            } else {
+                None
+            },
         })
     }
 }
 
-pub fn parse_paths(paths: Vec<String>, valid_exts: Vec<String>) -> Vec<FileInfo> {
+/// 将文件重命名为真实格式的扩展名，目标文件已存在时不处理
+fn fix_file_extension(path: &Path, real_ext: &str) -> Option<PathBuf> {
+    let fixed_path = path.with_extension(real_ext);
+    if fixed_path.exists() {
+        warn!(
+            "[FileInfo] -> Cannot fix extension of <{}>, <{}> already exists",
+            path.to_string_lossy(),
+            fixed_path.to_string_lossy()
+        );
+        return None;
+    }
+    match fs::rename(path, &fixed_path) {
+        Ok(()) => Some(fixed_path),
+        Err(e) => {
+            warn!(
+                "[FileInfo] -> Failed to fix extension of <{}>: {}",
+                path.to_string_lossy(),
+                e
+            );
+            None
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct FixedExtension {
+    pub path: PathBuf,
+    pub fixed_path: PathBuf,
+}
+
+/// 将扩展名与真实格式不一致的文件重命名为真实扩展名，返回成功重命名的文件
+pub fn fix_file_extensions(paths: &[PathBuf]) -> Vec<FixedExtension> {
+    paths
+        .iter()
+        .filter_map(|path| {
+            let real_ext = sniff_file_extension(path)?;
+            if !is_ext_mismatch(&get_file_extension(path), &real_ext) {
+                return None;
+            }
+            let fixed_path = fix_file_extension(path, &real_ext)?;
+            Some(FixedExtension {
+                path: path.clone(),
+                fixed_path,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_paths(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    options: &ScanOptions,
+) -> Vec<FileInfo> {
     paths
         .into_par_iter()
         .flat_map(|path| {
-            WalkDir::new(path)
-                .into_iter()
-                .filter_map(|e| e.ok())
+            let exts = normalize_exts(&valid_exts);
+            let root_dir = get_file_parent_dir(Path::new(&path));
+            walk_files(Path::new(&path), &valid_exts, options)
                 .par_bridge()
                 .filter_map(|entry: walkdir::DirEntry| {
-                    FileInfo::from_entry(&entry, valid_exts.clone())
+                    FileInfo::from_entry(&entry, &root_dir, &exts, options)
                 })
                 .collect::<Vec<_>>()
         })
         .collect()
 }
 
-pub fn is_symlink(entry: &DirEntry) -> bool {
-    entry.file_type().is_symlink()
+// 流式扫描时每批发送的文件数与最长间隔
+const SCAN_BATCH_SIZE: usize = 500;
+const SCAN_BATCH_INTERVAL: Duration = Duration::from_millis(200);
+
+// 正在进行的流式扫描，以 scan id 为键，值为取消标记
+static RUNNING_SCANS: Mutex<Vec<(String, Arc<AtomicBool>)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Default, Clone, Copy, serde::Serialize)]
+pub struct ScanTotals {
+    pub count: usize,
+    pub bytes_size: u64,
+    pub disk_size: u64,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "event", content = "data")]
+pub enum ScanEvent {
+    #[serde(rename = "batch")]
+    Batch {
+        scan_id: String,
+        files: Vec<FileInfo>,
+        totals: ScanTotals,
+    },
+    #[serde(rename = "finished")]
+    Finished {
+        scan_id: String,
+        totals: ScanTotals,
+        cancelled: bool,
+    },
 }
 
-pub fn count_valid_files(paths: Vec<String>, valid_exts: Vec<String>) -> usize {
-    let ext_set: HashSet<String> = valid_exts.iter().map(|ext| ext.to_lowercase()).collect();
+/// 边遍历边分批回调，`cancelled` 被置位后尽快停止，返回最终统计
+pub fn scan_paths_in_batches(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    options: &ScanOptions,
+    cancelled: &AtomicBool,
+    mut on_batch: impl FnMut(Vec<FileInfo>, ScanTotals),
+) -> ScanTotals {
+    let mut totals = ScanTotals::default();
+    let exts = normalize_exts(&valid_exts);
+    let mut flush = |entries: &mut Vec<(DirEntry, PathBuf)>, totals: &mut ScanTotals| {
+        if entries.is_empty() {
+            return;
+        }
+        let files: Vec<FileInfo> = entries
+            .par_drain(..)
+            .filter_map(|(entry, root_dir)| FileInfo::from_entry(&entry, &root_dir, &exts, options))
+            .collect();
+        for file in &files {
+            totals.count += 1;
+            totals.bytes_size += file.bytes_size;
+            totals.disk_size += file.disk_size;
+        }
+        if !files.is_empty() {
+            on_batch(files, *totals);
+        }
+    };
 
+    let mut entries = Vec::with_capacity(SCAN_BATCH_SIZE);
+    let mut last_flush = Instant::now();
+    'paths: for path in paths {
+        let root_dir = get_file_parent_dir(Path::new(&path));
+        for entry in walk_files(Path::new(&path), &valid_exts, options) {
+            if cancelled.load(Ordering::Relaxed) {
+                break 'paths;
+            }
+            entries.push((entry, root_dir.clone()));
+            if entries.len() >= SCAN_BATCH_SIZE || last_flush.elapsed() >= SCAN_BATCH_INTERVAL {
+                flush(&mut entries, &mut totals);
+                last_flush = Instant::now();
+            }
+        }
+    }
+    if !cancelled.load(Ordering::Relaxed) {
+        flush(&mut entries, &mut totals);
+    }
+    totals
+}
+
+pub fn count_valid_files(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    options: &ScanOptions,
+) -> usize {
+    let exts = normalize_exts(&valid_exts);
     paths
         .into_par_iter()
-        .flat_map_iter(|path| {
-            WalkDir::new(path)
-                .follow_links(false) // 禁止跟随符号链接
-                .into_iter()
-                .filter_entry(|e| !is_symlink(e)) // 过滤符号链接
-        })
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            // 筛选普通文件且扩展名匹配项
-            entry.file_type().is_file()
-                && entry
-                    .path()
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| ext_set.contains(&ext.to_lowercase()))
-                    .unwrap_or(false)
+        .map(|path| {
+            walk_files(Path::new(&path), &valid_exts, options)
+                .par_bridge()
+                .filter(|entry| sniff_valid_format(entry.path(), &exts).is_some())
+                .count()
         })
-        .count()
+        .sum()
 }
 
 pub fn format_file_size(bytes: u64) -> String {
@@ -203,17 +418,83 @@ pub fn is_file_in_directory(file_path: &str, dir_path: &str) -> Result<bool, io:
 }
 
 #[tauri::command]
-pub async fn ipc_parse_paths(paths: Vec<String>, valid_exts: Vec<String>) -> Response {
-    let data: Vec<FileInfo> = parse_paths(paths, valid_exts);
+pub async fn ipc_parse_paths(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    scan_options: Option<ScanOptions>,
+) -> Response {
+    let data: Vec<FileInfo> = parse_paths(paths, valid_exts, &scan_options.unwrap_or_default());
     Response::new(serde_json::to_string(&data).unwrap_or_default())
 }
 
+/// 在后台线程中流式扫描，立即返回 scan id，结果通过 `on_event` 分批推送
 #[tauri::command]
-pub async fn ipc_count_valid_files(paths: Vec<String>, valid_exts: Vec<String>) -> Response {
-    let count = count_valid_files(paths, valid_exts);
+pub async fn ipc_scan_paths(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    scan_options: Option<ScanOptions>,
+    on_event: Channel<ScanEvent>,
+) -> Result<String, String> {
+    let scan_id = nanoid!();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    RUNNING_SCANS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((scan_id.clone(), cancelled.clone()));
+
+    let options = scan_options.unwrap_or_default();
+    let id = scan_id.clone();
+    std::thread::spawn(move || {
+        let totals =
+            scan_paths_in_batches(paths, valid_exts, &options, &cancelled, |files, totals| {
+                let _ = on_event.send(ScanEvent::Batch {
+                    scan_id: id.clone(),
+                    files,
+                    totals,
+                });
+            });
+        RUNNING_SCANS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(scan_id, _)| scan_id != &id);
+        let _ = on_event.send(ScanEvent::Finished {
+            scan_id: id,
+            totals,
+            cancelled: cancelled.load(Ordering::Relaxed),
+        });
+    });
+
+    Ok(scan_id)
+}
+
+#[tauri::command]
+pub async fn ipc_cancel_scan(scan_id: String) -> Result<(), String> {
+    let scans = RUNNING_SCANS.lock().unwrap_or_else(|e| e.into_inner());
+    let (_, cancelled) = scans
+        .iter()
+        .find(|(id, _)| id == &scan_id)
+        .ok_or_else(|| format!("Scan not found: {}", scan_id))?;
+    cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn ipc_count_valid_files(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    scan_options: Option<ScanOptions>,
+) -> Response {
+    let count = count_valid_files(paths, valid_exts, &scan_options.unwrap_or_default());
     Response::new(count.to_string())
 }
 
+/// 由用户确认后调用，重命名磁盘上的文件
+#[tauri::command]
+pub async fn ipc_fix_file_extensions(paths: Vec<String>) -> Vec<FixedExtension> {
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    fix_file_extensions(&paths)
+}
+
 #[tauri::command]
 pub async fn ipc_is_file_in_directory(file_path: String, dir_path: String) -> Response {
     match is_file_in_directory(&file_path, &dir_path) {
@@ -222,7 +503,11 @@ pub async fn ipc_is_file_in_directory(file_path: String, dir_path: String) -> Re
     }
 }
 
-async fn copy_image(path: String, sidecar_origin: String) -> Result<(), Box<dyn Error>> {
+async fn copy_image(
+    path: String,
+    sidecar_origin: String,
+    config: &HttpConfig,
+) -> Result<(), Box<dyn Error>> {
     #[derive(serde::Deserialize)]
     struct RawPixelsResponse {
         width: usize,
@@ -233,7 +518,7 @@ async fn copy_image(path: String, sidecar_origin: String) -> Result<(), Box<dyn
     let origin = sidecar_origin.trim_end_matches('/');
     let url = format!("{}/api/codec/get-raw-pixels", origin);
 
-    let client = reqwest::Client::new();
+    let client = config.client()?;
     let resp = client
         .post(url)
         .json(&serde_json::json!({ "input_path": path }))
@@ -286,8 +571,12 @@ async fn copy_image(path: String, sidecar_origin: String) -> Result<(), Box<dyn
 }
 
 #[tauri::command]
-pub async fn ipc_copy_image(path: String, sidecar_origin: String) -> Response {
-    match copy_image(path, sidecar_origin).await {
+pub async fn ipc_copy_image<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    sidecar_origin: String,
+) -> Response {
+    match copy_image(path, sidecar_origin, &HttpConfig::from_store(&app)).await {
         Ok(_) => Response::new("{\"status\": \"success\"}".to_string()),
         Err(e) => Response::new(format!("{{\"status\": \"error\", \"message\": \"{}\"}}", e)),
     }
@@ -298,3 +587,37 @@ pub async fn ipc_get_file_name(path: String) -> Response {
     let file_name = get_file_name(Path::new(&path));
     Response::new(file_name)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use image::{ImageFormat, RgbImage};
+
+    fn write_image(path: &Path, format: ImageFormat) {
+        RgbImage::new(4, 4).save_with_format(path, format).unwrap();
+    }
+
+    #[test]
+    fn count_matches_parse_for_disguised_files() {
+        let root = temp_dir();
+        write_image(&root.join("real.png"), ImageFormat::Png);
+        // 伪装成 png 的 bmp 文件不计入
+        write_image(&root.join("disguised.png"), ImageFormat::Bmp);
+        // 扩展名不一致但真实格式同样允许
+        write_image(&root.join("jpeg.png"), ImageFormat::Jpeg);
+        let paths = vec![root.to_string_lossy().to_string()];
+        let exts = vec!["png".to_string(), "jpg".to_string()];
+        let options = ScanOptions::default();
+
+        let mut names: Vec<String> = parse_paths(paths.clone(), exts.clone(), &options)
+            .into_iter()
+            .map(|file| file.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["jpeg.png", "real.png"]);
+        assert_eq!(count_valid_files(paths, exts, &options), names.len());
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
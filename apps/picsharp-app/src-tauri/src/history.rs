@@ -0,0 +1,352 @@
+use crate::image_processor::common::CompressionResult;
+use crate::APP_IDENTIFIER;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+
+const JOBS_DIR_NAME: &str = "jobs";
+// 每行一条结果，逐个压缩时只追加，不重写整个文件
+const RECORD_EXT: &str = "jsonl";
+// 旧版本整体写入的记录
+const LEGACY_RECORD_EXT: &str = "json";
+// 超过保留天数或数量上限的旧任务记录会被删除
+const MAX_JOB_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+const MAX_JOB_COUNT: usize = 500;
+
+// 同一任务可能分多次写入（如逐个压缩），串行化追加与压缩
+static JOB_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to parse job record: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Job not found: {0}")]
+    NotFound(String),
+    #[error("Invalid job id: {0}")]
+    InvalidJobId(String),
+    #[error("Cannot resolve history directory: {0}")]
+    Dir(String),
+}
+
+type Result<T> = std::result::Result<T, HistoryError>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub job_id: String,
+    // 创建与最后更新时间（unix 秒）
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub results: Vec<CompressionResult>,
+}
+
+// 记录文件中的一行
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordLine {
+    recorded_at: u64,
+    result: CompressionResult,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 压缩任务结果记录，位于 `app_data_dir/jobs/<job_id>.jsonl`，用于任务结束后导出报告
+#[derive(Debug, Clone)]
+pub struct JobHistory {
+    dir: PathBuf,
+}
+
+impl JobHistory {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    pub fn from_app<R: Runtime>(app: &AppHandle<R>) -> Result<Self> {
+        let data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| HistoryError::Dir(e.to_string()))?;
+        Ok(Self::new(data_dir.join(JOBS_DIR_NAME)))
+    }
+
+    /// 不依赖 AppHandle 定位任务记录目录，供 CLI 使用
+    pub fn from_default_dir() -> Result<Self> {
+        let data_dir = dirs::data_dir()
+            .ok_or_else(|| HistoryError::Dir("data dir not available".to_string()))?;
+        Ok(Self::new(data_dir.join(APP_IDENTIFIER).join(JOBS_DIR_NAME)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn record_path(&self, job_id: &str, ext: &str) -> Result<PathBuf> {
+        // job id 来自前端，避免拼出目录外的路径
+        if job_id.is_empty()
+            || !job_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(HistoryError::InvalidJobId(job_id.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.{}", job_id, ext)))
+    }
+
+    /// 读取任务记录，同一输入文件的结果以最新一次为准，存在重复结果时顺便压缩文件
+    pub fn load(&self, job_id: &str) -> Result<JobRecord> {
+        let _guard = JOB_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let path = self.record_path(job_id, RECORD_EXT)?;
+        if !path.exists() {
+            let legacy_path = self.record_path(job_id, LEGACY_RECORD_EXT)?;
+            if legacy_path.exists() {
+                return Ok(serde_json::from_slice(&fs::read(legacy_path)?)?);
+            }
+            return Err(HistoryError::NotFound(job_id.to_string()));
+        }
+
+        let content = fs::read_to_string(&path)?;
+        let mut lines: Vec<RecordLine> = Vec::new();
+        let mut index_of: HashMap<String, usize> = HashMap::new();
+        let mut line_count = 0;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            line_count += 1;
+            // 写入中断时最后一行可能不完整，跳过无法解析的行
+            let line: RecordLine = match serde_json::from_str(line) {
+                Ok(line) => line,
+                Err(e) => {
+                    error!("Skip invalid job record line in <{}>: {}", job_id, e);
+                    continue;
+                }
+            };
+            match index_of.get(&line.result.input_path) {
+                Some(&index) => lines[index] = line,
+                None => {
+                    index_of.insert(line.result.input_path.clone(), lines.len());
+                    lines.push(line);
+                }
+            }
+        }
+        if lines.len() < line_count {
+            if let Err(e) = Self::write_lines(&path, &lines) {
+                error!("Failed to compact job record <{}>: {}", job_id, e);
+            }
+        }
+
+        let created_at = lines.iter().map(|line| line.recorded_at).min();
+        let updated_at = lines.iter().map(|line| line.recorded_at).max();
+        Ok(JobRecord {
+            job_id: job_id.to_string(),
+            created_at: created_at.unwrap_or_default(),
+            updated_at: updated_at.unwrap_or_default(),
+            results: lines.into_iter().map(|line| line.result).collect(),
+        })
+    }
+
+    fn write_lines(path: &Path, lines: &[RecordLine]) -> Result<()> {
+        let mut data = Vec::new();
+        for line in lines {
+            serde_json::to_writer(&mut data, line)?;
+            data.push(b'\n');
+        }
+        let temp_path = path.with_extension("jsonl.tmp");
+        fs::write(&temp_path, data)?;
+        fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    /// 追加任务结果，读取时同一输入文件的结果以最新一次为准
+    pub fn record(&self, job_id: &str, results: &[CompressionResult]) -> Result<()> {
+        if results.is_empty() {
+            return Ok(());
+        }
+        let path = self.record_path(job_id, RECORD_EXT)?;
+        let recorded_at = now_secs();
+        let mut data = Vec::new();
+        for result in results {
+            serde_json::to_writer(
+                &mut data,
+                &RecordLine {
+                    recorded_at,
+                    result: result.clone(),
+                },
+            )?;
+            data.push(b'\n');
+        }
+
+        let _guard = JOB_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.dir)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        // 上次写入中断时补上换行，避免不完整的行与新结果连在一起
+        let len = file.metadata()?.len();
+        if len > 0 {
+            let mut last = [0u8; 1];
+            file.seek(SeekFrom::Start(len - 1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                data.insert(0, b'\n');
+            }
+        }
+        // 一次写入整批数据，避免与其他进程的追加交错
+        file.write_all(&data)?;
+        Ok(())
+    }
+
+    /// 记录失败不影响压缩流程，只输出日志
+    pub fn record_or_log(&self, job_id: &str, results: &[CompressionResult]) {
+        if let Err(e) = self.record(job_id, results) {
+            error!("Failed to record job results: {}", e);
+        }
+    }
+
+    /// 按最后修改时间清理旧任务记录，保留最近 `max_count` 个且不超过 `max_age` 的记录
+    pub fn prune(&self, max_age: Duration, max_count: usize) -> Result<usize> {
+        let _guard = JOB_FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut records: Vec<(SystemTime, PathBuf)> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|ext| ext == RECORD_EXT || ext == LEGACY_RECORD_EXT)
+            })
+            .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+            .collect();
+        records.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for (index, (modified, path)) in records.iter().enumerate() {
+            let expired = now.duration_since(*modified).unwrap_or_default() > max_age;
+            if (index >= max_count || expired) && fs::remove_file(path).is_ok() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// 使用默认的保留策略清理，失败时只输出日志
+    pub fn prune_or_log(&self) {
+        match self.prune(MAX_JOB_AGE, MAX_JOB_COUNT) {
+            Ok(0) => {}
+            Ok(removed) => info!("Removed {} expired job records", removed),
+            Err(e) => error!("Failed to prune job records: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_processor::common::CompressionStatus;
+    use crate::test_support::temp_dir;
+
+    fn result(input_path: &str, compressed_bytes_size: u64) -> CompressionResult {
+        CompressionResult {
+            input_path: input_path.to_string(),
+            output_path: input_path.to_string(),
+            output_path_converted: String::new(),
+            compressed_bytes_size,
+            compressed_disk_size: compressed_bytes_size,
+            original_bytes_size: 100,
+            status: CompressionStatus::Success,
+            cost_time: 0,
+            compress_rate: 0.0,
+            error_message: None,
+            original_temp_path: String::new(),
+            job_id: "job".to_string(),
+            backup_id: None,
+        }
+    }
+
+    fn line_count(history: &JobHistory, job_id: &str) -> usize {
+        let path = history.record_path(job_id, RECORD_EXT).unwrap();
+        fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    #[test]
+    fn appends_results_and_keeps_latest_per_input() {
+        let history = JobHistory::new(temp_dir());
+        history.record("job", &[result("a.png", 50)]).unwrap();
+        history
+            .record("job", &[result("b.png", 60), result("a.png", 40)])
+            .unwrap();
+        assert_eq!(line_count(&history, "job"), 3);
+
+        let record = history.load("job").unwrap();
+        let sizes: Vec<(&str, u64)> = record
+            .results
+            .iter()
+            .map(|r| (r.input_path.as_str(), r.compressed_bytes_size))
+            .collect();
+        assert_eq!(sizes, vec![("a.png", 40), ("b.png", 60)]);
+        // 读取时压缩重复的结果
+        assert_eq!(line_count(&history, "job"), 2);
+        let _ = fs::remove_dir_all(history.dir());
+    }
+
+    #[test]
+    fn skips_truncated_lines_and_reads_legacy_records() {
+        let history = JobHistory::new(temp_dir());
+        history.record("job", &[result("a.png", 50)]).unwrap();
+        let path = history.record_path("job", RECORD_EXT).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"recorded_at\":1,\"res").unwrap();
+        history.record("job", &[result("b.png", 60)]).unwrap();
+        assert_eq!(history.load("job").unwrap().results.len(), 2);
+
+        let legacy = JobRecord {
+            job_id: "legacy".to_string(),
+            created_at: 1,
+            updated_at: 2,
+            results: vec![result("c.png", 10)],
+        };
+        fs::write(
+            history.record_path("legacy", LEGACY_RECORD_EXT).unwrap(),
+            serde_json::to_vec(&legacy).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            history.load("legacy").unwrap().results[0].input_path,
+            "c.png"
+        );
+        assert!(matches!(
+            history.load("missing"),
+            Err(HistoryError::NotFound(_))
+        ));
+        assert!(matches!(
+            history.record("../escape", &[result("a.png", 1)]),
+            Err(HistoryError::InvalidJobId(_))
+        ));
+        let _ = fs::remove_dir_all(history.dir());
+    }
+
+    #[test]
+    fn prunes_records_beyond_max_count() {
+        let history = JobHistory::new(temp_dir());
+        for job_id in ["a", "b", "c"] {
+            history.record(job_id, &[result("x.png", 1)]).unwrap();
+        }
+        assert_eq!(history.prune(MAX_JOB_AGE, 2).unwrap(), 1);
+        assert_eq!(fs::read_dir(history.dir()).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(history.dir());
+    }
+}
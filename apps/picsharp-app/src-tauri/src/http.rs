@@ -0,0 +1,404 @@
+use log::warn;
+use rand::Rng;
+use reqwest::{
+    Certificate, Client, ClientBuilder, NoProxy, Proxy, RequestBuilder, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_READ_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+// 本机的服务（如 sidecar）总是直连，不经过代理
+const LOCAL_NO_PROXY: &str = "localhost,127.0.0.1,::1";
+
+// 复用同一配置下的客户端，保持连接池
+static SHARED_CLIENT: Mutex<Option<(ClientKey, Client)>> = Mutex::new(None);
+
+/// 失败重试策略，等待时间按 base_delay * 2^n 递增并加入随机抖动
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    // 包含首次请求在内的最大尝试次数
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // 429 的 Retry-After 超过该值时不再等待，直接返回响应
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 attempt 次（从 1 开始）失败后的等待时间
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        // 0.5 ~ 1.0 倍的随机抖动，避免并发请求同时重试
+        exp.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HttpError {
+    #[error("Failed to build HTTP client: {0}")]
+    Client(#[from] reqwest::Error),
+    #[error("Invalid proxy settings: {0}")]
+    Proxy(String),
+    #[error("Failed to load root certificate {0}: {1}")]
+    Certificate(String, String),
+}
+
+/// HTTP(S) 代理设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    // 如 http://proxy.example.com:8080，也可以在 URL 中带上用户名与密码
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // 逗号分隔的直连地址，支持域名、IP 与 CIDR
+    pub no_proxy: String,
+}
+
+/// 所有外部请求共用的网络配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpConfig {
+    pub connect_timeout: Duration,
+    // 两次读取数据之间的最长间隔，不限制整个请求的耗时，避免大文件上传超时
+    pub read_timeout: Duration,
+    pub retry: RetryPolicy,
+    pub proxy: Option<ProxyConfig>,
+    // 额外信任的根证书文件（PEM 或 DER），用于企业网络中的自签 CA
+    pub root_certificates: Vec<PathBuf>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            read_timeout: Duration::from_secs(DEFAULT_READ_TIMEOUT_SECS),
+            retry: RetryPolicy::default(),
+            proxy: None,
+            root_certificates: Vec::new(),
+        }
+    }
+}
+
+// 决定客户端是否需要重建的配置项
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClientKey {
+    connect_timeout: Duration,
+    read_timeout: Duration,
+    proxy: Option<ProxyConfig>,
+    root_certificates: Vec<PathBuf>,
+}
+
+fn string_setting<R: Runtime>(store: &tauri_plugin_store::Store<R>, key: &str) -> Option<String> {
+    store
+        .get(key)
+        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
+        .filter(|s| !s.is_empty())
+}
+
+fn load_certificates(path: &PathBuf) -> Result<Vec<Certificate>, HttpError> {
+    let to_error = |e: String| HttpError::Certificate(path.display().to_string(), e);
+    let data = std::fs::read(path).map_err(|e| to_error(e.to_string()))?;
+    let certificates = if data.starts_with(b"-----BEGIN") {
+        Certificate::from_pem_bundle(&data)
+    } else {
+        Certificate::from_der(&data).map(|cert| vec![cert])
+    };
+    certificates.map_err(|e| to_error(e.to_string()))
+}
+
+fn build_proxy(config: &ProxyConfig) -> Result<Proxy, HttpError> {
+    let mut proxy = Proxy::all(config.url.as_str())
+        .map_err(|e| HttpError::Proxy(format!("{}: {}", config.url, e)))?;
+    if let Some(username) = config.username.as_deref().filter(|u| !u.is_empty()) {
+        proxy = proxy.basic_auth(username, config.password.as_deref().unwrap_or_default());
+    }
+    let no_proxy = if config.no_proxy.trim().is_empty() {
+        LOCAL_NO_PROXY.to_string()
+    } else {
+        format!("{},{}", LOCAL_NO_PROXY, config.no_proxy)
+    };
+    Ok(proxy.no_proxy(NoProxy::from_string(&no_proxy)))
+}
+
+impl HttpConfig {
+    pub fn from_store<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let mut config = Self::default();
+        let Ok(store) = app.store("settings.json") else {
+            return config;
+        };
+        if let Some(secs) = store
+            .get("network_connect_timeout")
+            .and_then(|v| v.as_u64())
+            .filter(|secs| *secs > 0)
+        {
+            config.connect_timeout = Duration::from_secs(secs);
+        }
+        if let Some(secs) = store
+            .get("network_read_timeout")
+            .and_then(|v| v.as_u64())
+            .filter(|secs| *secs > 0)
+        {
+            config.read_timeout = Duration::from_secs(secs);
+        }
+        if let Some(attempts) = store.get("network_max_attempts").and_then(|v| v.as_u64()) {
+            config.retry.max_attempts = attempts.clamp(1, 10) as u32;
+        }
+        if let Some(url) = string_setting(&store, "network_proxy_url") {
+            config.proxy = Some(ProxyConfig {
+                url,
+                username: string_setting(&store, "network_proxy_username"),
+                password: string_setting(&store, "network_proxy_password"),
+                no_proxy: string_setting(&store, "network_no_proxy").unwrap_or_default(),
+            });
+        }
+        if let Some(paths) = store
+            .get("network_root_certificates")
+            .and_then(|v| serde_json::from_value::<Vec<String>>(v).ok())
+        {
+            config.root_certificates = paths
+                .iter()
+                .map(|path| path.trim())
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+                .collect();
+        }
+        config
+    }
+
+    fn client_key(&self) -> ClientKey {
+        ClientKey {
+            connect_timeout: self.connect_timeout,
+            read_timeout: self.read_timeout,
+            proxy: self.proxy.clone(),
+            root_certificates: self.root_certificates.clone(),
+        }
+    }
+
+    /// 应用超时、代理与根证书设置的 ClientBuilder，供需要额外定制的请求使用
+    pub fn client_builder(&self) -> Result<ClientBuilder, HttpError> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout);
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(build_proxy(proxy)?);
+        }
+        for path in &self.root_certificates {
+            for certificate in load_certificates(path)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(builder)
+    }
+
+    /// 返回共享的客户端，配置变化时重新创建
+    pub fn client(&self) -> Result<Client, HttpError> {
+        let key = self.client_key();
+        let mut shared = SHARED_CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_key, client)) = shared.as_ref() {
+            if cached_key == &key {
+                return Ok(client.clone());
+            }
+        }
+        let client = self.client_builder()?.build()?;
+        *shared = Some((key, client.clone()));
+        Ok(client)
+    }
+}
+
+// 只支持秒数形式的 Retry-After
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+// 超时或发送中断时服务端可能已经处理了请求，非幂等请求只在连接失败时重试
+fn is_retryable_error(error: &reqwest::Error, idempotent: bool) -> bool {
+    error.is_connect()
+        || (idempotent && (error.is_timeout() || error.is_request() || error.is_body()))
+}
+
+/// 发送请求，5xx 与网络错误按指数退避重试，429 按 Retry-After 等待后重试。
+/// `idempotent` 为 false 时（如 POST /shrink）网络错误只重试连接失败。
+/// 每次尝试都通过 `build` 重新构建请求，以便重新打开上传的文件流。
+/// 重试次数用完后返回最后一次的响应或错误，由调用方处理状态码。
+pub async fn send_with_retry<F, Fut, E>(
+    policy: &RetryPolicy,
+    idempotent: bool,
+    mut build: F,
+) -> std::result::Result<Response, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<RequestBuilder, E>>,
+    E: From<reqwest::Error>,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let is_last = attempt >= max_attempts;
+        let delay = match build().await?.send().await {
+            Ok(response) => {
+                let status = response.status();
+                if is_last {
+                    return Ok(response);
+                }
+                if status.is_server_error() {
+                    policy.backoff(attempt)
+                } else if status == StatusCode::TOO_MANY_REQUESTS {
+                    // 没有 Retry-After 的 429 通常是额度用完，重试没有意义
+                    match retry_after(&response) {
+                        Some(delay) if delay <= policy.max_retry_after => delay,
+                        _ => return Ok(response),
+                    }
+                } else {
+                    return Ok(response);
+                }
+            }
+            Err(e) if !is_last && is_retryable_error(&e, idempotent) => policy.backoff(attempt),
+            Err(e) => return Err(e.into()),
+        };
+        warn!(
+            "[http] -> Request failed (attempt {}/{}), retrying in {:?}",
+            attempt, max_attempts, delay
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{StubResponse, StubServer};
+    use std::time::Instant;
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+
+    async fn send(
+        policy: &RetryPolicy,
+        client: &Client,
+        url: &str,
+        idempotent: bool,
+    ) -> Result<Response, reqwest::Error> {
+        send_with_retry(policy, idempotent, || async {
+            Ok::<_, reqwest::Error>(client.post(url))
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_up_to_max_attempts() {
+        let server = StubServer::sequence(vec![StubResponse::new(503)]);
+        let response = send(&fast_policy(), &Client::new(), &server.url, true)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn waits_for_retry_after_on_429() {
+        let server = StubServer::sequence(vec![
+            StubResponse::new(429).header("Retry-After", "1"),
+            StubResponse::new(200),
+        ]);
+        let started = Instant::now();
+        let response = send(&fast_policy(), &Client::new(), &server.url, true)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn returns_429_when_retry_after_exceeds_cap() {
+        let server =
+            StubServer::sequence(vec![StubResponse::new(429).header("Retry-After", "120")]);
+        let response = send(&fast_policy(), &Client::new(), &server.url, true)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn returns_429_without_retry_after() {
+        let server = StubServer::sequence(vec![StubResponse::new(429)]);
+        let response = send(&fast_policy(), &Client::new(), &server.url, true)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let server = StubServer::sequence(vec![StubResponse::new(401), StubResponse::new(200)]);
+        let response = send(&fast_policy(), &Client::new(), &server.url, true)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_timeouts_only_for_idempotent_requests() {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(200))
+            .build()
+            .unwrap();
+        let slow = || {
+            StubServer::sequence(vec![
+                StubResponse::new(200).delay(Duration::from_millis(600)),
+                StubResponse::new(200),
+            ])
+        };
+
+        let server = slow();
+        assert!(send(&fast_policy(), &client, &server.url, false)
+            .await
+            .is_err());
+        assert_eq!(server.requests().len(), 1);
+
+        let server = slow();
+        let response = send(&fast_policy(), &client, &server.url, true)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.requests().len(), 2);
+    }
+}
@@ -0,0 +1,440 @@
+use crate::file::{parse_paths, FileInfo};
+use crate::image_processor::common::{
+    has_transparency, is_likely_screenshot, might_contain_text, open_image,
+};
+use crate::image_processor::info::{read_image_info, ImageInfo};
+use crate::report::{escape_html, format_bytes, html_cards, html_document, ReportFormat};
+use crate::scan::ScanOptions;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 常见页面中图片的最大显示尺寸（2x 屏幕下的全宽图）
+pub const DEFAULT_MAX_DIMENSION: u32 = 2560;
+// 估算质量大于等于该值的 JPEG 视为几乎未压缩
+pub const DEFAULT_JPEG_QUALITY_THRESHOLD: u8 = 98;
+
+// libjpeg 标准亮度量化表，用于反推 JPEG 的质量
+const STD_LUMINANCE_QUANT_TABLE: [u32; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditOptions {
+    // 宽或高超过该值时建议缩小
+    pub max_dimension: u32,
+    pub jpeg_quality_threshold: u8,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            max_dimension: DEFAULT_MAX_DIMENSION,
+            jpeg_quality_threshold: DEFAULT_JPEG_QUALITY_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditIssue {
+    // 尺寸超过显示需要
+    #[serde(rename = "oversized")]
+    Oversized,
+    // 以 PNG 保存的照片
+    #[serde(rename = "photo_as_png")]
+    PhotoAsPng,
+    // 没有透明像素的 PNG（截图、文字等）
+    #[serde(rename = "opaque_png")]
+    OpaquePng,
+    // 以接近 100 的质量保存的 JPEG
+    #[serde(rename = "max_quality_jpeg")]
+    MaxQualityJpeg,
+    // 扩展名与真实格式不一致
+    #[serde(rename = "ext_mismatch")]
+    ExtMismatch,
+}
+
+impl AuditIssue {
+    fn label(&self) -> &'static str {
+        match self {
+            AuditIssue::Oversized => "Oversized",
+            AuditIssue::PhotoAsPng => "Photo saved as PNG",
+            AuditIssue::OpaquePng => "PNG without transparency",
+            AuditIssue::MaxQualityJpeg => "JPEG at maximum quality",
+            AuditIssue::ExtMismatch => "Extension mismatch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditAction {
+    #[serde(rename = "keep")]
+    Keep,
+    // 以正常质量重新压缩
+    #[serde(rename = "recompress")]
+    Recompress,
+    #[serde(rename = "resize")]
+    Resize,
+    #[serde(rename = "convert")]
+    Convert,
+    #[serde(rename = "resize_and_convert")]
+    ResizeAndConvert,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileAudit {
+    pub path: PathBuf,
+    pub name: String,
+    pub ext: String,
+    pub bytes_size: u64,
+    pub width: u32,
+    pub height: u32,
+    // 根据量化表估算的 JPEG 质量
+    pub jpeg_quality: Option<u8>,
+    // 仅对 PNG 解码检测
+    pub has_transparency: Option<bool>,
+    pub issues: Vec<AuditIssue>,
+    pub action: AuditAction,
+    // 建议转换的目标格式
+    pub target_ext: Option<String>,
+    // 建议缩小到的最大边长
+    pub target_max_dimension: Option<u32>,
+    pub recommendation: String,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditReport {
+    pub files: Vec<FileAudit>,
+    pub total_count: usize,
+    pub flagged_count: usize,
+    pub total_bytes_size: u64,
+    pub flagged_bytes_size: u64,
+    // 生成时间，Unix 秒
+    pub generated_at: u64,
+    pub options: AuditOptions,
+}
+
+fn read_u16_be(reader: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+// 读取 JPEG 中编号为 0 的量化表（亮度表）
+fn read_luminance_quant_table(path: &Path) -> io::Result<Option<[u16; 64]>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if read_u16_be(&mut reader)? != 0xFFD8 {
+        return Ok(None);
+    }
+    loop {
+        let mut marker = [0u8; 2];
+        reader.read_exact(&mut marker)?;
+        if marker[0] != 0xFF {
+            return Ok(None);
+        }
+        // 填充字节
+        if marker[1] == 0xFF {
+            continue;
+        }
+        // 图像数据开始，不会再出现量化表
+        if marker[1] == 0xDA || marker[1] == 0xD9 {
+            return Ok(None);
+        }
+        let length = read_u16_be(&mut reader)?.saturating_sub(2) as usize;
+        let mut segment = vec![0u8; length];
+        reader.read_exact(&mut segment)?;
+        if marker[1] != 0xDB {
+            continue;
+        }
+        // 一个 DQT 段中可以包含多张表
+        let mut offset = 0;
+        while offset < segment.len() {
+            let precision = segment[offset] >> 4;
+            let table_id = segment[offset] & 0x0F;
+            offset += 1;
+            let entry_size = if precision == 0 { 1 } else { 2 };
+            if offset + 64 * entry_size > segment.len() {
+                return Ok(None);
+            }
+            if table_id == 0 {
+                let mut table = [0u16; 64];
+                for (i, value) in table.iter_mut().enumerate() {
+                    let start = offset + i * entry_size;
+                    *value = if entry_size == 1 {
+                        segment[start] as u16
+                    } else {
+                        u16::from_be_bytes([segment[start], segment[start + 1]])
+                    };
+                }
+                return Ok(Some(table));
+            }
+            offset += 64 * entry_size;
+        }
+    }
+}
+
+/// 按 libjpeg 的缩放规则，根据亮度量化表反推 JPEG 的质量
+pub fn estimate_jpeg_quality(path: &Path) -> Option<u8> {
+    let table = read_luminance_quant_table(path).ok()??;
+    if table.iter().all(|&value| value <= 1) {
+        return Some(100);
+    }
+    let sum: u32 = table.iter().map(|&value| value as u32).sum();
+    let std_sum: u32 = STD_LUMINANCE_QUANT_TABLE.iter().sum();
+    let scale = sum as f64 * 100.0 / std_sum as f64;
+    let quality = if scale <= 100.0 {
+        (200.0 - scale) / 2.0
+    } else {
+        5000.0 / scale
+    };
+    Some(quality.round().clamp(1.0, 100.0) as u8)
+}
+
+fn scaled_dimensions(width: u32, height: u32, max_dimension: u32) -> (u32, u32) {
+    let ratio = max_dimension as f64 / width.max(height) as f64;
+    (
+        ((width as f64 * ratio).round() as u32).max(1),
+        ((height as f64 * ratio).round() as u32).max(1),
+    )
+}
+
+/// 根据文件头信息与 `common` 中的启发式规则检查单个文件，给出建议的处理方式
+pub fn audit_file(file: &FileInfo, options: &AuditOptions) -> FileAudit {
+    let ext = if file.real_ext.is_empty() {
+        file.ext.clone()
+    } else {
+        file.real_ext.clone()
+    };
+    let mut audit = FileAudit {
+        path: file.path.clone(),
+        name: file.name.clone(),
+        ext: ext.clone(),
+        bytes_size: file.bytes_size,
+        width: 0,
+        height: 0,
+        jpeg_quality: None,
+        has_transparency: None,
+        issues: Vec::new(),
+        action: AuditAction::Keep,
+        target_ext: None,
+        target_max_dimension: None,
+        recommendation: String::from("No action needed"),
+        error_message: None,
+    };
+
+    let info: Option<ImageInfo> = file
+        .image_info
+        .clone()
+        .or_else(|| read_image_info(&file.path));
+    let Some(info) = info else {
+        audit.error_message = Some(String::from("Failed to read image header"));
+        audit.recommendation = String::from("Unable to analyze this file");
+        return audit;
+    };
+    audit.width = info.width;
+    audit.height = info.height;
+
+    if file.ext_mismatch {
+        audit.issues.push(AuditIssue::ExtMismatch);
+    }
+
+    let oversized = info.width.max(info.height) > options.max_dimension;
+    if oversized {
+        audit.issues.push(AuditIssue::Oversized);
+        audit.target_max_dimension = Some(options.max_dimension);
+    }
+
+    match ext.as_str() {
+        // 动图转换格式会丢失动画，只检查尺寸
+        "png" if !info.is_animated => match open_image(&file.path) {
+            Ok(img) => {
+                let transparent = has_transparency(&img);
+                audit.has_transparency = Some(transparent);
+                if !transparent {
+                    // 尺寸过小时边缘检测没有意义
+                    let has_sharp_edges = img.width() > 2
+                        && img.height() > 2
+                        && (is_likely_screenshot(&img) || might_contain_text(&img));
+                    if has_sharp_edges {
+                        // 截图和文字在有损 JPEG 中会出现明显噪点
+                        audit.issues.push(AuditIssue::OpaquePng);
+                        audit.target_ext = Some(String::from("webp"));
+                    } else {
+                        audit.issues.push(AuditIssue::PhotoAsPng);
+                        audit.target_ext = Some(String::from("jpg"));
+                    }
+                }
+            }
+            Err(e) => audit.error_message = Some(e.to_string()),
+        },
+        "jpg" | "jpeg" => {
+            audit.jpeg_quality = estimate_jpeg_quality(&file.path);
+            if audit
+                .jpeg_quality
+                .is_some_and(|quality| quality >= options.jpeg_quality_threshold)
+            {
+                audit.issues.push(AuditIssue::MaxQualityJpeg);
+            }
+        }
+        _ => {}
+    }
+
+    let mut steps = Vec::new();
+    if let Some(max_dimension) = audit.target_max_dimension {
+        let (width, height) = scaled_dimensions(info.width, info.height, max_dimension);
+        steps.push(format!(
+            "Resize from {}x{} to {}x{}",
+            info.width, info.height, width, height
+        ));
+    }
+    if let Some(target_ext) = &audit.target_ext {
+        steps.push(format!(
+            "Convert to {}{}",
+            target_ext.to_uppercase(),
+            if target_ext == "webp" {
+                " to keep edges sharp"
+            } else {
+                ""
+            }
+        ));
+    } else if audit.issues.contains(&AuditIssue::MaxQualityJpeg) {
+        steps.push(format!(
+            "Recompress at normal quality (currently ~{})",
+            audit.jpeg_quality.unwrap_or(100)
+        ));
+    }
+    if audit.issues.contains(&AuditIssue::ExtMismatch) {
+        steps.push(format!("Rename to .{}", ext));
+    }
+
+    audit.action = match (oversized, audit.target_ext.is_some()) {
+        (true, true) => AuditAction::ResizeAndConvert,
+        (true, false) => AuditAction::Resize,
+        (false, true) => AuditAction::Convert,
+        (false, false) if audit.issues.contains(&AuditIssue::MaxQualityJpeg) => {
+            AuditAction::Recompress
+        }
+        _ => AuditAction::Keep,
+    };
+    if !steps.is_empty() {
+        audit.recommendation = steps.join("; ");
+    }
+    audit
+}
+
+pub fn audit_files(files: &[FileInfo], options: &AuditOptions) -> AuditReport {
+    let mut audits: Vec<FileAudit> = files
+        .par_iter()
+        .map(|file| audit_file(file, options))
+        .collect();
+    // 问题最多、体积最大的文件排在前面
+    audits.sort_by(|a, b| {
+        b.issues
+            .len()
+            .cmp(&a.issues.len())
+            .then_with(|| b.bytes_size.cmp(&a.bytes_size))
+    });
+
+    let flagged = audits.iter().filter(|audit| !audit.issues.is_empty());
+    AuditReport {
+        total_count: audits.len(),
+        flagged_count: flagged.clone().count(),
+        total_bytes_size: audits.iter().map(|audit| audit.bytes_size).sum(),
+        flagged_bytes_size: flagged.map(|audit| audit.bytes_size).sum(),
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        options: options.clone(),
+        files: audits,
+    }
+}
+
+/// 生成 HTML 报告，只列出存在问题的文件
+pub fn render_audit_html(report: &AuditReport) -> String {
+    let mut body = html_cards(&[
+        ("Images", report.total_count.to_string()),
+        ("Flagged", report.flagged_count.to_string()),
+        (
+            "Total size",
+            escape_html(&format_bytes(report.total_bytes_size)),
+        ),
+        (
+            "Flagged size",
+            escape_html(&format_bytes(report.flagged_bytes_size)),
+        ),
+    ]);
+
+    body.push_str("<h2>Flagged images</h2>");
+    body.push_str("<table><thead><tr><th>File</th><th>Size</th><th>Dimensions</th><th>Issues</th><th>Recommended action</th></tr></thead><tbody>");
+    for audit in report.files.iter().filter(|audit| !audit.issues.is_empty()) {
+        let issues: String = audit
+            .issues
+            .iter()
+            .map(|issue| format!("<span class=\"tag warn\">{}</span>", issue.label()))
+            .collect();
+        let _ = write!(
+            body,
+            "<tr><td title=\"{path}\">{name}</td><td class=\"num\">{size}</td><td class=\"num\">{width}x{height}</td><td>{issues}</td><td>{recommendation}</td></tr>",
+            path = escape_html(&audit.path.to_string_lossy()),
+            name = escape_html(&audit.name),
+            size = escape_html(&format_bytes(audit.bytes_size)),
+            width = audit.width,
+            height = audit.height,
+            issues = issues,
+            recommendation = escape_html(&audit.recommendation),
+        );
+    }
+    body.push_str("</tbody></table>");
+
+    html_document(
+        "PicSharp Image Audit",
+        &format!(
+            "Max dimension {}px · JPEG quality threshold {}",
+            report.options.max_dimension, report.options.jpeg_quality_threshold
+        ),
+        &body,
+    )
+}
+
+/// 检查报告只支持 JSON 与 HTML
+pub fn render_audit_report(report: &AuditReport, format: ReportFormat) -> Result<String, String> {
+    match format {
+        ReportFormat::Json => Ok(serde_json::to_string_pretty(report).unwrap_or_default()),
+        ReportFormat::Html => Ok(render_audit_html(report)),
+        ReportFormat::Csv => Err("CSV is not supported for audit reports".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_audit_images(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    scan_options: Option<ScanOptions>,
+    audit_options: Option<AuditOptions>,
+) -> Result<AuditReport, String> {
+    // 扫描、解析 DQT 与解码 PNG 都是阻塞操作，放到阻塞线程中执行
+    tokio::task::spawn_blocking(move || {
+        let files = parse_paths(paths, valid_exts, &scan_options.unwrap_or_default());
+        audit_files(&files, &audit_options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn ipc_export_audit_report(
+    report: AuditReport,
+    format: ReportFormat,
+    path: String,
+) -> Result<(), String> {
+    fs::write(&path, render_audit_report(&report, format)?).map_err(|e| e.to_string())
+}
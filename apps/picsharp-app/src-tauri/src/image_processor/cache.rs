@@ -0,0 +1,156 @@
+use crate::APP_IDENTIFIER;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+
+const CACHE_FILE_NAME: &str = "optimized_cache.json";
+// 超过该数量时淘汰最早的记录
+const MAX_ENTRIES: usize = 200_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    // 压缩参数指纹
+    fingerprint: String,
+    // 记录时间（unix 秒）
+    recorded_at: u64,
+}
+
+/// 记录“该内容是 PicSharp 在某组参数下的输出”，以内容哈希 + 参数指纹为键，
+/// 再次处理同一文件时直接跳过，避免重复有损压缩。
+/// 应用内所有任务共享同一个实例（保存在 Tauri state 中），保存时与磁盘上的记录合并，
+/// 避免 CLI 等其他进程写入的记录被覆盖。
+#[derive(Debug)]
+pub struct OptimizedCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    // 有未保存的记录
+    dirty: AtomicBool,
+    // 同一时间只有一个线程写文件，值为上次读写后文件的修改时间
+    saving: Mutex<Option<SystemTime>>,
+}
+
+fn cache_key(hash: &str, fingerprint: &str) -> String {
+    format!("{}:{}", hash, fingerprint)
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn read_entries(path: &Path) -> HashMap<String, CacheEntry> {
+    match fs::read(path) {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+            error!("[OptimizedCache] -> Failed to parse cache file: {}", e);
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+impl OptimizedCache {
+    pub fn load(path: PathBuf) -> Self {
+        let entries = read_entries(&path);
+        Self {
+            saving: Mutex::new(modified_at(&path)),
+            path,
+            entries: Mutex::new(entries),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// 在应用启动时创建，之后通过 `from_app` 取得共享实例
+    pub fn init<R: Runtime>(app: &AppHandle<R>) {
+        match app.path().app_data_dir() {
+            Ok(dir) => {
+                app.manage(Arc::new(Self::load(dir.join(CACHE_FILE_NAME))));
+            }
+            Err(e) => error!("[OptimizedCache] -> Failed to get app data dir: {}", e),
+        }
+    }
+
+    pub fn from_app<R: Runtime>(app: &AppHandle<R>) -> Option<Arc<Self>> {
+        app.try_state::<Arc<Self>>()
+            .map(|cache| cache.inner().clone())
+    }
+
+    /// 不依赖 AppHandle 定位缓存文件，供 CLI 使用
+    pub fn from_default_dir() -> Option<Arc<Self>> {
+        dirs::data_dir()
+            .map(|dir| Arc::new(Self::load(dir.join(APP_IDENTIFIER).join(CACHE_FILE_NAME))))
+    }
+
+    pub fn contains(&self, hash: &str, fingerprint: &str) -> bool {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.contains_key(&cache_key(hash, fingerprint))
+    }
+
+    pub fn record(&self, hash: &str, fingerprint: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(
+            cache_key(hash, fingerprint),
+            CacheEntry {
+                fingerprint: fingerprint.to_string(),
+                recorded_at: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            },
+        );
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// 有新记录时保存，文件在上次读写后被其他进程修改过时先合并其中的记录
+    pub fn save(&self) -> io::Result<()> {
+        let mut last_modified = self.saving.lock().unwrap_or_else(|e| e.into_inner());
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+        let on_disk = match modified_at(&self.path) {
+            modified if modified != *last_modified => read_entries(&self.path),
+            _ => HashMap::new(),
+        };
+        let snapshot = {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            for (key, entry) in on_disk {
+                entries.entry(key).or_insert(entry);
+            }
+            Self::evict(&mut entries);
+            entries.clone()
+        };
+        let result = Self::write(&self.path, &snapshot);
+        match result {
+            Ok(()) => *last_modified = modified_at(&self.path),
+            Err(_) => self.dirty.store(true, Ordering::Release),
+        }
+        result
+    }
+
+    fn evict(entries: &mut HashMap<String, CacheEntry>) {
+        if entries.len() > MAX_ENTRIES {
+            let mut recorded: Vec<(String, u64)> = entries
+                .iter()
+                .map(|(key, entry)| (key.clone(), entry.recorded_at))
+                .collect();
+            recorded.sort_by_key(|(_, recorded_at)| *recorded_at);
+            for (key, _) in recorded.iter().take(entries.len() - MAX_ENTRIES) {
+                entries.remove(key);
+            }
+        }
+    }
+
+    fn write(path: &Path, entries: &HashMap<String, CacheEntry>) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_vec(entries)?)?;
+        fs::rename(temp_path, path)
+    }
+}
@@ -1,8 +1,11 @@
-use image::{imageops, DynamicImage, GenericImageView};
+use crate::file::get_file_hash;
+use image::{imageops, DynamicImage, GenericImageView, ImageReader};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -20,10 +23,19 @@ pub enum CompressionError {
     FileNotFound(String),
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SkipReason {
+    // 文件已是 PicSharp 以相同参数压缩的输出
+    AlreadyOptimized,
+    // 输出文件已存在且冲突策略为跳过
+    OutputExists,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CompressionStatus {
-    Success = 0,
-    Failed = 1,
+    Success,
+    Failed,
+    Skipped(SkipReason),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -38,6 +50,9 @@ pub enum QualityMode {
 pub struct CompressionResult {
     pub input_path: String,
     pub status: CompressionStatus,
+    // 压缩前的文件字节数
+    #[serde(default)]
+    pub original_bytes_size: u64,
     pub output_path: String,
     pub output_path_converted: String,
     pub compressed_bytes_size: u64,
@@ -46,6 +61,8 @@ pub struct CompressionResult {
     pub compress_rate: f64,
     pub error_message: Option<String>,
     pub original_temp_path: String,
+    pub job_id: String,
+    pub backup_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +88,29 @@ impl std::str::FromStr for CompressionOutputMode {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CollisionPolicy {
+    #[serde(rename = "overwrite")]
+    Overwrite,
+    #[serde(rename = "skip")]
+    Skip,
+    #[serde(rename = "auto_number")]
+    AutoNumber,
+}
+
+impl std::str::FromStr for CollisionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "overwrite" => Ok(Self::Overwrite),
+            "skip" => Ok(Self::Skip),
+            "auto_number" => Ok(Self::AutoNumber),
+            _ => Err(format!("Unknown collision policy: {}", s)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressionOptions {
     // 输出模式
@@ -79,6 +119,13 @@ pub struct CompressionOptions {
     pub output_mode_save_as_file_suffix: String,
     // 输出模式为save_to_folder时，输出文件夹路径
     pub output_mode_save_to_folder: String,
+    // 拖入路径的所在目录，输出到新文件夹时保留文件相对于它的目录结构
+    #[serde(default)]
+    pub source_roots: Vec<PathBuf>,
+    // 输出文件名模板，为空时按输出模式使用默认模板
+    pub output_filename_template: String,
+    // 输出文件已存在时的处理方式
+    pub output_collision_policy: CollisionPolicy,
     // 是否限制压缩率阈值
     pub save_compress_rate_limit: bool,
     // 压缩率阈值
@@ -89,6 +136,29 @@ pub struct CompressionOptions {
     pub quality_level: u8,
     // 压缩模式
     pub quality_mode: QualityMode,
+    // 跳过已由 PicSharp 以相同参数压缩过的文件
+    pub skip_already_optimized: bool,
+}
+
+impl CompressionOptions {
+    /// 影响压缩输出内容的参数指纹，输出路径等设置不参与计算
+    pub fn fingerprint(&self) -> String {
+        let mode = match self.quality_mode {
+            QualityMode::Lossless => "lossless",
+            QualityMode::Lossy => "lossy",
+        };
+        format!("local:{}:{}", mode, self.quality_level)
+    }
+
+    /// 是否保留压缩后的数据：文件必须变小，开启压缩率限制时压缩比例（0-1）不低于阈值。
+    /// 不保留时输出原图，本地引擎、远程服务与预估都按该规则取舍。
+    pub fn keeps_compressed(&self, original_size: u64, compressed_size: u64) -> bool {
+        if compressed_size >= original_size {
+            return false;
+        }
+        let ratio = (original_size - compressed_size) as f64 / original_size as f64;
+        !self.save_compress_rate_limit || ratio >= self.save_compress_rate_limit_threshold
+    }
 }
 
 impl Default for CompressionOptions {
@@ -97,6 +167,9 @@ impl Default for CompressionOptions {
             output_mode: CompressionOutputMode::Overwrite,
             output_mode_save_as_file_suffix: "_compressed".to_string(),
             output_mode_save_to_folder: "".to_string(),
+            source_roots: Vec::new(),
+            output_filename_template: "".to_string(),
+            output_collision_policy: CollisionPolicy::AutoNumber,
             save_compress_rate_limit: false,
             save_compress_rate_limit_threshold: 0.2,
             retain_metadata: vec![
@@ -106,6 +179,7 @@ impl Default for CompressionOptions {
             ],
             quality_level: 4,
             quality_mode: QualityMode::Lossy,
+            skip_already_optimized: true,
         }
     }
 }
@@ -116,35 +190,188 @@ pub fn calculate_compress_rate(original_size: u64, compressed_size: u64) -> f64
     (rate * 100.0).round() / 100.0
 }
 
+/// 按文件头识别格式并解码图片，扩展名与真实格式不一致时也能正确读取
+pub fn open_image(input_path: &Path) -> Result<DynamicImage, CompressionError> {
+    ImageReader::open(input_path)?
+        .with_guessed_format()?
+        .decode()
+        .map_err(|e| CompressionError::ImageProcessing(e.to_string()))
+}
+
+pub const SAVE_AS_NEW_FILE_TEMPLATE: &str = "{stem}{suffix}.{ext}";
+pub const SAVE_TO_NEW_FOLDER_TEMPLATE: &str = "{stem}.{ext}";
+
+// unix 秒转换为 UTC 日期 YYYYMMDD
+fn format_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}{:02}{:02}", year, month, day)
+}
+
+/// 渲染输出文件名模板，支持 {stem} {suffix} {ext} {parent} {hash8} {date}，
+/// 模板中可以包含 `/` 以生成子目录
+pub fn render_output_template(
+    template: &str,
+    input_path: &Path,
+    options: &CompressionOptions,
+) -> PathBuf {
+    let stem = input_path.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = input_path.extension().unwrap_or_default().to_string_lossy();
+    let parent = input_path
+        .parent()
+        .and_then(|p| p.file_name())
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    let mut rendered = template
+        .replace("{stem}", &stem)
+        .replace("{suffix}", &options.output_mode_save_as_file_suffix)
+        .replace("{ext}", &ext)
+        .replace("{parent}", &parent);
+    if rendered.contains("{hash8}") {
+        let hash = get_file_hash(input_path).unwrap_or_default();
+        rendered = rendered.replace("{hash8}", hash.get(..8).unwrap_or(&hash));
+    }
+    if rendered.contains("{date}") {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        rendered = rendered.replace("{date}", &format_date(now));
+    }
+
+    // 没有扩展名时去掉模板末尾多余的 `.`
+    let rendered = rendered.trim_end_matches('.');
+    rendered
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect()
+}
+
+/// 文件所在目录相对于最近的拖入根目录的路径，不在任何根目录下时为空
+pub fn relative_source_dir(input_path: &Path, source_roots: &[PathBuf]) -> PathBuf {
+    let parent = input_path.parent().unwrap_or_else(|| Path::new(""));
+    source_roots
+        .iter()
+        .filter_map(|root| parent.strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count())
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
 // 获取输出路径
 pub fn get_output_path(input_path: &Path, options: &CompressionOptions) -> PathBuf {
+    let template = |default_template: &'static str| {
+        if options.output_filename_template.is_empty() {
+            default_template.to_string()
+        } else {
+            options.output_filename_template.clone()
+        }
+    };
     match options.output_mode {
         CompressionOutputMode::SaveToNewFolder
             if !options.output_mode_save_to_folder.is_empty() =>
         {
-            let file_name = input_path.file_name().unwrap_or_default();
-            Path::new(&options.output_mode_save_to_folder).join(file_name)
+            let template = template(SAVE_TO_NEW_FOLDER_TEMPLATE);
+            // 模板中含 {parent} 时由模板决定目录结构，不再保留相对目录，避免目录重复
+            let relative_dir = if template.contains("{parent}") {
+                PathBuf::new()
+            } else {
+                relative_source_dir(input_path, &options.source_roots)
+            };
+            Path::new(&options.output_mode_save_to_folder)
+                .join(relative_dir)
+                .join(render_output_template(&template, input_path, options))
         }
         CompressionOutputMode::SaveAsNewFile => {
             let parent = input_path.parent().unwrap_or_else(|| Path::new(""));
-            let file_stem = input_path.file_stem().unwrap_or_default();
-            let ext = input_path.extension().unwrap_or_default();
-
-            parent.join(format!(
-                "{}{}{}",
-                file_stem.to_string_lossy(),
-                options.output_mode_save_as_file_suffix,
-                if ext.is_empty() {
-                    "".to_string()
-                } else {
-                    format!(".{}", ext.to_string_lossy())
-                }
+            parent.join(render_output_template(
+                &template(SAVE_AS_NEW_FILE_TEMPLATE),
+                input_path,
+                options,
             ))
         }
         _ => input_path.to_path_buf(),
     }
 }
 
+// 批量压缩中已被占用、尚未写入完成的输出路径
+static RESERVED_OUTPUT_PATHS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// 占用的输出路径，释放时从占用列表中移除
+#[derive(Debug)]
+pub struct OutputPathGuard {
+    path: PathBuf,
+}
+
+impl OutputPathGuard {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for OutputPathGuard {
+    fn drop(&mut self) {
+        let mut reserved = RESERVED_OUTPUT_PATHS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        reserved.retain(|p| p != &self.path);
+    }
+}
+
+fn numbered_path(path: &Path, n: u32) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{}_{}.{}", stem, n, ext.to_string_lossy()),
+        None => format!("{}_{}", stem, n),
+    };
+    path.with_file_name(file_name)
+}
+
+/// 按冲突策略确定最终输出路径，返回 None 表示跳过。
+/// 同一批次中并行处理的文件不会拿到同一个输出路径。
+/// 只有覆盖模式可以写回原文件；其他模式下模板渲染出原文件路径时视为冲突，
+/// 自动编号时另存为编号文件，否则跳过，原文件不会被覆盖。
+pub fn claim_output_path(
+    input_path: &Path,
+    output_path: PathBuf,
+    options: &CompressionOptions,
+) -> Option<OutputPathGuard> {
+    let mut reserved = RESERVED_OUTPUT_PATHS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let is_taken = |path: &Path| reserved.iter().any(|p| p == path) || path.exists();
+    let overwrites_input = output_path == input_path;
+    let overwrite_mode = matches!(options.output_mode, CompressionOutputMode::Overwrite);
+
+    let free = if overwrites_input {
+        overwrite_mode
+    } else {
+        !is_taken(&output_path)
+    };
+    let path = if free {
+        output_path
+    } else {
+        match options.output_collision_policy {
+            CollisionPolicy::Overwrite if !overwrites_input => output_path,
+            CollisionPolicy::Overwrite | CollisionPolicy::Skip => return None,
+            CollisionPolicy::AutoNumber => (1..)
+                .map(|n| numbered_path(&output_path, n))
+                .find(|path| !is_taken(path))?,
+        }
+    };
+
+    reserved.push(path.clone());
+    Some(OutputPathGuard { path })
+}
+
 const RIFF_HEADER: &[u8] = b"RIFF";
 const WEBP_HEADER: &[u8] = b"WEBP";
 const ANIM_CHUNK: &[u8] = b"ANIM";
@@ -331,3 +558,146 @@ pub fn might_contain_text(img: &DynamicImage) -> bool {
     // 文本图像通常有较高比例的边缘，但不会过高（区别于噪点图像）
     edge_ratio > 0.1 && edge_ratio < 0.3
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_file;
+
+    fn options(output_mode: CompressionOutputMode, policy: CollisionPolicy) -> CompressionOptions {
+        CompressionOptions {
+            output_mode,
+            output_collision_policy: policy,
+            ..CompressionOptions::default()
+        }
+    }
+
+    #[test]
+    fn renders_template_placeholders() {
+        let options = CompressionOptions::default();
+        let input_path = Path::new("/photos/icons/logo.png");
+        assert_eq!(
+            render_output_template("{parent}/{stem}{suffix}.{ext}", input_path, &options),
+            Path::new("icons").join("logo_compressed.png")
+        );
+        // 没有扩展名时去掉末尾的 `.`
+        assert_eq!(
+            render_output_template("{stem}.{ext}", Path::new("/photos/README"), &options),
+            PathBuf::from("README")
+        );
+        let dated = render_output_template("{stem}-{date}", input_path, &options);
+        let dated = dated.to_string_lossy();
+        assert_eq!(dated.len(), "logo-".len() + 8);
+        assert!(dated["logo-".len()..].chars().all(|c| c.is_ascii_digit()));
+    }
+
+    #[test]
+    fn strips_parent_and_empty_segments_from_template() {
+        let options = CompressionOptions::default();
+        let input_path = Path::new("/photos/logo.png");
+        assert_eq!(
+            render_output_template("../../{stem}.{ext}", input_path, &options),
+            PathBuf::from("logo.png")
+        );
+        assert_eq!(
+            render_output_template("/out/./a\\..\\{stem}.{ext}", input_path, &options),
+            Path::new("out").join("a").join("logo.png")
+        );
+    }
+
+    #[test]
+    fn keeps_relative_dirs_in_new_folder_unless_template_has_parent() {
+        let mut options = options(
+            CompressionOutputMode::SaveToNewFolder,
+            CollisionPolicy::Skip,
+        );
+        options.output_mode_save_to_folder = "/out".to_string();
+        options.source_roots = vec![PathBuf::from("/photos")];
+        let input_path = Path::new("/photos/assets/icons/x.png");
+
+        assert_eq!(
+            get_output_path(input_path, &options),
+            Path::new("/out").join("assets").join("icons").join("x.png")
+        );
+        options.output_filename_template = "{parent}/{stem}.{ext}".to_string();
+        assert_eq!(
+            get_output_path(input_path, &options),
+            Path::new("/out").join("icons").join("x.png")
+        );
+        // 不在拖入目录下的文件直接输出到目标文件夹
+        options.output_filename_template = String::new();
+        assert_eq!(
+            get_output_path(Path::new("/other/x.png"), &options),
+            Path::new("/out").join("x.png")
+        );
+    }
+
+    #[test]
+    fn formats_utc_dates() {
+        assert_eq!(format_date(0), "19700101");
+        assert_eq!(format_date(951_782_400), "20000229");
+        assert_eq!(format_date(1_704_067_199), "20231231");
+    }
+
+    #[test]
+    fn auto_numbers_or_skips_existing_outputs() {
+        let input_path = temp_file("png", b"input");
+        let existing = temp_file("png", b"existing");
+
+        let skip = options(CompressionOutputMode::SaveAsNewFile, CollisionPolicy::Skip);
+        assert!(claim_output_path(&input_path, existing.clone(), &skip).is_none());
+
+        let overwrite = options(
+            CompressionOutputMode::SaveAsNewFile,
+            CollisionPolicy::Overwrite,
+        );
+        let guard = claim_output_path(&input_path, existing.clone(), &overwrite).unwrap();
+        assert_eq!(guard.path(), existing);
+        drop(guard);
+
+        let auto = options(
+            CompressionOutputMode::SaveAsNewFile,
+            CollisionPolicy::AutoNumber,
+        );
+        let first = claim_output_path(&input_path, existing.clone(), &auto).unwrap();
+        assert_eq!(first.path(), numbered_path(&existing, 1));
+        // 尚未写入但已被占用的路径同样跳过
+        let second = claim_output_path(&input_path, existing.clone(), &auto).unwrap();
+        assert_eq!(second.path(), numbered_path(&existing, 2));
+        drop(first);
+        let third = claim_output_path(&input_path, existing.clone(), &auto).unwrap();
+        assert_eq!(third.path(), numbered_path(&existing, 1));
+
+        let _ = fs::remove_file(input_path);
+        let _ = fs::remove_file(existing);
+    }
+
+    #[test]
+    fn never_writes_new_file_over_input() {
+        let input_path = temp_file("png", b"input");
+
+        let overwrite_mode = options(CompressionOutputMode::Overwrite, CollisionPolicy::Skip);
+        let guard = claim_output_path(&input_path, input_path.clone(), &overwrite_mode).unwrap();
+        assert_eq!(guard.path(), input_path);
+        drop(guard);
+
+        // `{stem}.{ext}` 模板在另存为新文件模式下渲染出原文件路径
+        let mut new_file = options(
+            CompressionOutputMode::SaveAsNewFile,
+            CollisionPolicy::Overwrite,
+        );
+        new_file.output_filename_template = "{stem}.{ext}".to_string();
+        let output_path = get_output_path(&input_path, &new_file);
+        assert_eq!(output_path, input_path);
+        assert!(claim_output_path(&input_path, output_path.clone(), &new_file).is_none());
+
+        new_file.output_collision_policy = CollisionPolicy::Skip;
+        assert!(claim_output_path(&input_path, output_path.clone(), &new_file).is_none());
+
+        new_file.output_collision_policy = CollisionPolicy::AutoNumber;
+        let guard = claim_output_path(&input_path, output_path, &new_file).unwrap();
+        assert_eq!(guard.path(), numbered_path(&input_path, 1));
+
+        let _ = fs::remove_file(input_path);
+    }
+}
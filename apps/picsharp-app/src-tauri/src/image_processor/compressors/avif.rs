@@ -1,6 +1,5 @@
-use crate::image_processor::common::CompressionError;
 use crate::image_processor::common::QualityMode;
-use image;
+use crate::image_processor::common::{open_image, CompressionError};
 use ravif::{AlphaColorMode, Encoder, Img};
 use rgb::RGBA;
 use std::fs;
@@ -10,9 +9,7 @@ pub fn lossless_compress_avif(
     input_path: &Path,
     output_path: &Path,
 ) -> Result<(), CompressionError> {
-    let img = image::open(input_path)
-        .map_err(|e| CompressionError::ImageProcessing(e.to_string()))?
-        .to_rgba8();
+    let img = open_image(input_path)?.to_rgba8();
 
     let width = img.width() as usize;
     let height = img.height() as usize;
@@ -41,9 +38,7 @@ pub fn lossy_compress_avif(
     output_path: &Path,
     level: u8,
 ) -> Result<(), CompressionError> {
-    let img = image::open(input_path)
-        .map_err(|e| CompressionError::ImageProcessing(e.to_string()))?
-        .to_rgba8();
+    let img = open_image(input_path)?.to_rgba8();
 
     log::info!("lossy_compress_avif: {:?} {:?}", input_path, output_path);
 
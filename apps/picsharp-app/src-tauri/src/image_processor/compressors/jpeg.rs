@@ -1,16 +1,11 @@
-use crate::image_processor::common::CompressionError;
-use image;
+use crate::image_processor::common::{open_image, CompressionError};
 use mozjpeg;
 use std::fs;
 use std::path::Path;
 
-pub fn compress_jpeg(
-    input_path: &Path,
-    output_path: &Path,
-    level: u8,
-) -> Result<(), CompressionError> {
-    let img =
-        image::open(input_path).map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
+/// 压缩JPEG并返回编码后的数据，不写入磁盘
+pub fn encode_jpeg(input_path: &Path, level: u8) -> Result<Vec<u8>, CompressionError> {
+    let img = open_image(input_path)?;
 
     let rgb = img.to_rgb8();
     let width = rgb.width() as usize;
@@ -42,7 +37,15 @@ pub fn compress_jpeg(
     .map_err(|_| CompressionError::ImageProcessing("JPEG compression failed".to_string()))?
     .map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
 
-    fs::write(output_path, buffer).map_err(|e| CompressionError::Io(e))?;
+    Ok(buffer)
+}
 
+pub fn compress_jpeg(
+    input_path: &Path,
+    output_path: &Path,
+    level: u8,
+) -> Result<(), CompressionError> {
+    let buffer = encode_jpeg(input_path, level)?;
+    fs::write(output_path, buffer).map_err(|e| CompressionError::Io(e))?;
     Ok(())
 }
@@ -4,15 +4,22 @@ pub mod png;
 pub mod svg;
 pub mod webp;
 
+use crate::backup::BackupStore;
 use crate::file::{
-    convert_file_src, get_file_bytes_size, get_file_disk_size, get_file_extension, get_file_name,
+    convert_file_src, get_bytes_hash, get_file_bytes_size, get_file_disk_size, get_file_extension,
+    get_file_hash, sniff_file_extension,
 };
+use crate::history::JobHistory;
+use crate::image_processor::cache::OptimizedCache;
 use crate::image_processor::common::QualityMode;
 use crate::image_processor::common::{
-    get_output_path, CompressionError, CompressionOptions, CompressionOutputMode,
-    CompressionResult, CompressionStatus,
+    claim_output_path, get_output_path, CollisionPolicy, CompressionError, CompressionOptions,
+    CompressionOutputMode, CompressionResult, CompressionStatus, SkipReason,
 };
+use crate::watcher::mark_self_written;
+use crate::APP_IDENTIFIER;
 use log::{error, info};
+use nanoid::nanoid;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -22,66 +29,29 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
 use tauri::Emitter;
-use tauri::{AppHandle, Manager, Runtime};
+use tauri::{AppHandle, Runtime};
 use tauri_plugin_store::StoreExt;
 
 use super::common::calculate_compress_rate;
 
-fn compress_single_image(
+// 本地引擎支持压缩的文件扩展名
+pub const LOCAL_SUPPORTED_EXTS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+
+/// 按文件头识别的真实格式选择压缩器，无法识别时按扩展名，返回编码后的数据，不写入磁盘
+pub fn encode_image(
     input_path: &Path,
-    temp_dir: &PathBuf,
     options: &CompressionOptions,
-) -> Result<CompressionResult, String> {
-    let start = Instant::now();
-    let input_path_str = input_path.to_string_lossy().to_string();
-
-    if !input_path.exists() {
-        return Err(CompressionError::FileNotFound(input_path_str).to_string());
-    }
-
-    let original_bytes_size = get_file_bytes_size(&input_path_str, None).unwrap_or(0);
-
-    let output_path = get_output_path(input_path, options);
-    let output_path_str = output_path.to_string_lossy().to_string();
-
-    if let Some(parent) = output_path.parent() {
-        if let Err(e) = fs::create_dir_all(parent) {
-            return Err(CompressionError::Io(e).to_string());
-        }
-    }
-
-    let mut original_temp_path = String::new();
-
-    if temp_dir.exists() {
-        let name = get_file_name(Path::new(input_path));
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        let random_num = rand::random::<u32>() % 10000;
-        original_temp_path = temp_dir
-            .join(format!("{}_{}_{}", timestamp, random_num, name))
-            .to_string_lossy()
-            .to_string();
-        match std::fs::copy(input_path, original_temp_path.clone()) {
-            Ok(_) => {}
-            Err(e) => {
-                error!("Failed to copy file to temp path: {}", e);
-            }
-        }
-    }
-
-    let result = match get_file_extension(input_path).as_str() {
-        "png" => png::compress_png(
+) -> Result<Vec<u8>, CompressionError> {
+    let ext = sniff_file_extension(input_path).unwrap_or_else(|| get_file_extension(input_path));
+    match ext.as_str() {
+        "png" => png::encode_png(
             input_path,
-            &output_path,
             options.quality_level,
             Some(options.quality_mode),
         ),
-        "jpg" | "jpeg" => jpeg::compress_jpeg(input_path, &output_path, options.quality_level),
-        "webp" => webp::compress_webp(
+        "jpg" | "jpeg" => jpeg::encode_jpeg(input_path, options.quality_level),
+        "webp" => webp::encode_webp(
             input_path,
-            &output_path,
             options.quality_level,
             Some(options.quality_mode),
         ),
@@ -93,10 +63,237 @@ fn compress_single_image(
         // ),
         // "svg" => svg::compress_svg(input_path, &output_path),
         ext => Err(CompressionError::UnsupportedFormat(ext.to_string())),
+    }
+}
+
+/// 一次压缩任务中各文件共享的上下文
+#[derive(Debug, Default)]
+pub struct CompressionContext {
+    pub job_id: String,
+    pub backup_store: Option<BackupStore>,
+    // 所有任务共享的缓存
+    pub cache: Option<Arc<OptimizedCache>>,
+    pub history: Option<JobHistory>,
+}
+
+impl CompressionContext {
+    pub fn from_app<R: Runtime>(app: &AppHandle<R>, job_id: Option<String>) -> Self {
+        let backup_store = match BackupStore::from_app(app) {
+            Ok(store) => Some(store),
+            Err(e) => {
+                error!("Failed to get backup store: {}", e);
+                None
+            }
+        };
+        let history = match JobHistory::from_app(app) {
+            Ok(history) => Some(history),
+            Err(e) => {
+                error!("Failed to get job history: {}", e);
+                None
+            }
+        };
+        Self {
+            job_id: job_id.unwrap_or_else(|| nanoid!()),
+            backup_store,
+            cache: OptimizedCache::from_app(app),
+            history,
+        }
+    }
+
+    /// 不依赖 AppHandle 创建上下文，供 CLI 使用
+    pub fn from_default_dir(job_id: Option<String>) -> Self {
+        let backup_store = match BackupStore::from_default_dir() {
+            Ok(store) => Some(store),
+            Err(e) => {
+                error!("Failed to get backup store: {}", e);
+                None
+            }
+        };
+        // CLI 每次运行都是新进程，在此清理旧任务记录
+        let history = match JobHistory::from_default_dir() {
+            Ok(history) => {
+                history.prune_or_log();
+                Some(history)
+            }
+            Err(e) => {
+                error!("Failed to get job history: {}", e);
+                None
+            }
+        };
+        Self {
+            job_id: job_id.unwrap_or_else(|| nanoid!()),
+            backup_store,
+            cache: OptimizedCache::from_default_dir(),
+            history,
+        }
+    }
+
+    /// 保存任务结果，供之后导出报告
+    pub fn record_results(&self, results: &[CompressionResult]) {
+        if let Some(history) = &self.history {
+            history.record_or_log(&self.job_id, results);
+        }
+    }
+
+    /// 任务结束后持久化缓存
+    pub fn finish(&self) {
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.save() {
+                error!("Failed to save optimized cache: {}", e);
+            }
+        }
+    }
+}
+
+pub fn compress_single_image(
+    input_path: &Path,
+    context: &CompressionContext,
+    options: &CompressionOptions,
+) -> Result<CompressionResult, String> {
+    let start = Instant::now();
+
+    if !input_path.exists() {
+        return Err(
+            CompressionError::FileNotFound(input_path.to_string_lossy().to_string()).to_string(),
+        );
+    }
+
+    if let Some(result) = already_optimized_result(input_path, context, options, start) {
+        return Ok(result);
+    }
+
+    write_compressed(input_path, context, options, start, || {
+        encode_image(input_path, options)
+    })
+}
+
+/// 文件已由 PicSharp 以相同参数压缩过时返回跳过结果
+pub(crate) fn already_optimized_result(
+    input_path: &Path,
+    context: &CompressionContext,
+    options: &CompressionOptions,
+    start: Instant,
+) -> Option<CompressionResult> {
+    if !options.skip_already_optimized {
+        return None;
+    }
+    let cache = context.cache.as_ref()?;
+    let hash = get_file_hash(input_path).ok()?;
+    if !cache.contains(&hash, &options.fingerprint()) {
+        return None;
+    }
+
+    let input_path_str = input_path.to_string_lossy().to_string();
+    info!("Skip already optimized file: {}", input_path_str);
+    let original_bytes_size = get_file_bytes_size(&input_path_str, None).unwrap_or(0);
+    Some(CompressionResult {
+        input_path: input_path_str.clone(),
+        output_path_converted: convert_file_src(&input_path_str).unwrap_or_default(),
+        compressed_bytes_size: original_bytes_size,
+        compressed_disk_size: get_file_disk_size(&input_path_str, None).unwrap_or(0),
+        output_path: input_path_str,
+        status: CompressionStatus::Skipped(SkipReason::AlreadyOptimized),
+        original_bytes_size,
+        error_message: None,
+        compress_rate: 0.0,
+        cost_time: start.elapsed().as_millis() as u64,
+        original_temp_path: "".to_string(),
+        job_id: context.job_id.clone(),
+        backup_id: None,
+    })
+}
+
+/// 记录压缩输出的哈希；输出到其他路径时原图不变，同时记录原图的哈希，
+/// 再次处理同一原图时也能识别为已压缩，不再生成新的编号文件
+pub(crate) fn record_optimized(
+    context: &CompressionContext,
+    options: &CompressionOptions,
+    input_path: &Path,
+    output_path: &Path,
+    output_hash: &str,
+) {
+    let Some(cache) = &context.cache else {
+        return;
+    };
+    let fingerprint = options.fingerprint();
+    cache.record(output_hash, &fingerprint);
+    if output_path != input_path {
+        match get_file_hash(input_path) {
+            Ok(input_hash) => cache.record(&input_hash, &fingerprint),
+            Err(e) => error!("Failed to hash original file: {}", e),
+        }
+    }
+}
+
+/// 占用输出路径、备份原图并写入 `encode` 返回的数据
+pub(crate) fn write_compressed<F>(
+    input_path: &Path,
+    context: &CompressionContext,
+    options: &CompressionOptions,
+    start: Instant,
+    encode: F,
+) -> Result<CompressionResult, String>
+where
+    F: FnOnce() -> Result<Vec<u8>, CompressionError>,
+{
+    let input_path_str = input_path.to_string_lossy().to_string();
+    let original_bytes_size = get_file_bytes_size(&input_path_str, None).unwrap_or(0);
+
+    // 持有占用直到写入完成，避免并行任务写入同一个输出路径
+    let Some(output_guard) =
+        claim_output_path(input_path, get_output_path(input_path, options), options)
+    else {
+        info!("Skip file because output exists: {}", input_path_str);
+        return Ok(CompressionResult {
+            input_path: input_path_str.clone(),
+            output_path_converted: convert_file_src(&input_path_str).unwrap_or_default(),
+            compressed_bytes_size: original_bytes_size,
+            compressed_disk_size: get_file_disk_size(&input_path_str, None).unwrap_or(0),
+            output_path: input_path_str,
+            status: CompressionStatus::Skipped(SkipReason::OutputExists),
+            original_bytes_size,
+            error_message: None,
+            compress_rate: 0.0,
+            cost_time: start.elapsed().as_millis() as u64,
+            original_temp_path: "".to_string(),
+            job_id: context.job_id.clone(),
+            backup_id: None,
+        });
     };
+    let output_path = output_guard.path();
+    let output_path_str = output_path.to_string_lossy().to_string();
+
+    if let Some(parent) = output_path.parent() {
+        if let Err(e) = fs::create_dir_all(parent) {
+            return Err(CompressionError::Io(e).to_string());
+        }
+    }
+
+    // 只备份将被替换的文件，备份失败时不覆盖
+    let backup = match &context.backup_store {
+        Some(store) => store
+            .backup_replaced(output_path, &context.job_id)
+            .map_err(|e| format!("Failed to backup original file: {}", e))?,
+        None => None,
+    };
+
+    // 返回写入内容的哈希，压缩结果不达标时输出原图
+    let result = encode().and_then(|data| {
+        if options.keeps_compressed(original_bytes_size, data.len() as u64) {
+            fs::write(output_path, &data).map_err(CompressionError::Io)?;
+            mark_self_written(output_path);
+            return Ok(get_bytes_hash(&data));
+        }
+        if output_path != input_path {
+            fs::copy(input_path, output_path).map_err(CompressionError::Io)?;
+            mark_self_written(output_path);
+        }
+        get_file_hash(output_path).map_err(CompressionError::Io)
+    });
 
     match result {
-        Ok(_) => {
+        Ok(hash) => {
+            record_optimized(context, options, input_path, output_path, &hash);
             let compressed_bytes_size = get_file_bytes_size(&output_path_str, None).unwrap();
             let compressed_disk_size = get_file_disk_size(&output_path_str, None).unwrap();
             let duration = start.elapsed();
@@ -107,10 +304,21 @@ fn compress_single_image(
                 compressed_disk_size,
                 output_path: output_path_str.clone(),
                 status: CompressionStatus::Success,
+                original_bytes_size,
                 error_message: None,
                 compress_rate: calculate_compress_rate(original_bytes_size, compressed_bytes_size),
                 cost_time: duration.as_millis() as u64,
-                original_temp_path: convert_file_src(&original_temp_path).unwrap_or_default(),
+                // 原图未被覆盖时直接指向原图，供界面对比与撤销
+                original_temp_path: if output_path != input_path {
+                    convert_file_src(&input_path_str).unwrap_or_default()
+                } else {
+                    backup
+                        .as_ref()
+                        .and_then(|entry| convert_file_src(&entry.backup_path).ok())
+                        .unwrap_or_default()
+                },
+                job_id: context.job_id.clone(),
+                backup_id: backup.map(|entry| entry.id),
             };
 
             Ok(compress_result)
@@ -119,61 +327,75 @@ fn compress_single_image(
     }
 }
 
-fn get_store_value<R: Runtime, T: serde::de::DeserializeOwned>(
-    store: &tauri_plugin_store::Store<R>,
+fn get_store_value<T: serde::de::DeserializeOwned>(
+    store: &impl Fn(&str) -> Option<Value>,
     key: &str,
     default_value: Value,
 ) -> T {
-    let store_value = store.get(key);
+    let store_value = store(key);
     let value = store_value.unwrap_or_else(|| default_value.clone());
     serde_json::from_value(value).unwrap_or_else(|_| {
         serde_json::from_value(default_value).expect("默认值应该是可反序列化的")
     })
 }
 
-fn create_compression_options_from_store<R: Runtime>(
+pub(crate) fn create_compression_options_from_store<R: Runtime>(
     app: &AppHandle<R>,
 ) -> Result<CompressionOptions, String> {
     let store = app.store("settings.json").map_err(|e| e.to_string())?;
+    Ok(compression_options_from_settings(&|key| store.get(key)))
+}
+
+/// 不依赖 AppHandle 读取 GUI 保存的设置文件，供 CLI 使用，与 GUI 的压缩参数保持一致
+pub fn compression_options_from_default_dir() -> CompressionOptions {
+    let settings: serde_json::Map<String, Value> = dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER).join("settings.json"))
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default();
+    compression_options_from_settings(&|key| settings.get(key).cloned())
+}
 
+// 按设置项生成压缩选项，未设置的项使用默认值
+fn compression_options_from_settings(store: &impl Fn(&str) -> Option<Value>) -> CompressionOptions {
     let quality_level: u8 = get_store_value(
-        &store,
+        store,
         "compression_local_quality_level",
         Value::Number(serde_json::Number::from(4)),
     );
 
-    let quality_mode = get_store_value::<R, QualityMode>(
-        &store,
+    let quality_mode = get_store_value::<QualityMode>(
+        store,
         "compression_local_quality_mode",
         Value::String("lossy".to_string()),
     );
 
     let compression_tasks_output_mode: String = get_store_value(
-        &store,
+        store,
         "compression_tasks_output_mode",
         Value::String("overwrite".to_string()),
     );
 
     let output_mode_save_as_file_suffix: String = get_store_value(
-        &store,
+        store,
         "compression_tasks_output_mode_save_as_file_suffix",
         Value::String("_compressed".to_string()),
     );
 
     let output_mode_save_to_folder: String = get_store_value(
-        &store,
+        store,
         "compression_tasks_output_mode_save_to_folder",
         Value::String("".to_string()),
     );
 
     let save_compress_rate_limit: bool = get_store_value(
-        &store,
+        store,
         "compression_tasks_save_compress_rate_limit",
         Value::Bool(false),
     );
 
     let save_compress_rate_limit_threshold: f64 = get_store_value(
-        &store,
+        store,
         "compression_tasks_save_compress_rate_limit_threshold",
         Value::Number(serde_json::Number::from_f64(0.2).unwrap()),
     );
@@ -185,46 +407,54 @@ fn create_compression_options_from_store<R: Runtime>(
     ]);
 
     let compression_retain_metadata_array: Vec<String> =
-        get_store_value(&store, "compression_retain_metadata", default_metadata);
+        get_store_value(store, "compression_retain_metadata", default_metadata);
 
-    Ok(CompressionOptions {
+    let output_filename_template: String = get_store_value(
+        store,
+        "compression_output_filename_template",
+        Value::String("".to_string()),
+    );
+
+    let output_collision_policy: String = get_store_value(
+        store,
+        "compression_output_collision_policy",
+        Value::String("auto_number".to_string()),
+    );
+
+    let skip_already_optimized: bool = get_store_value(
+        store,
+        "compression_skip_already_optimized",
+        Value::Bool(true),
+    );
+
+    CompressionOptions {
         output_mode: CompressionOutputMode::from_str(&compression_tasks_output_mode)
             .unwrap_or(CompressionOutputMode::Overwrite),
         output_mode_save_as_file_suffix,
         output_mode_save_to_folder,
+        source_roots: Vec::new(),
+        output_filename_template,
+        output_collision_policy: CollisionPolicy::from_str(&output_collision_policy)
+            .unwrap_or(CollisionPolicy::AutoNumber),
         save_compress_rate_limit,
         save_compress_rate_limit_threshold,
         retain_metadata: compression_retain_metadata_array,
         quality_level,
         quality_mode,
-    })
+        skip_already_optimized,
+    }
 }
 
-fn process_compression_tasks<R: Runtime>(
-    app: &AppHandle<R>,
+pub(crate) fn process_compression_tasks(
     webview_window: &tauri::WebviewWindow,
     paths: Vec<PathBuf>,
+    context: &CompressionContext,
     options: Arc<CompressionOptions>,
 ) -> Vec<CompressionResult> {
-    let temp_dir = match app.path().app_cache_dir() {
-        Ok(path) => {
-            let temp_dir = path.join("picsharp_temp");
-            if temp_dir.exists() {
-                temp_dir
-            } else {
-                fs::create_dir_all(temp_dir.clone()).unwrap();
-                temp_dir
-            }
-        }
-        Err(e) => {
-            error!("Failed to get temp dir: {}", e);
-            PathBuf::new()
-        }
-    };
-    paths
+    let results: Vec<CompressionResult> = paths
         .par_iter()
         .map(|path| {
-            let result = match compress_single_image(path, &temp_dir, &options) {
+            let result = match compress_single_image(path, context, &options) {
                 Ok(result) => result,
                 Err(e) => CompressionResult {
                     input_path: path.to_string_lossy().to_string(),
@@ -233,10 +463,14 @@ fn process_compression_tasks<R: Runtime>(
                     compressed_disk_size: 0,
                     output_path: "".to_string(),
                     status: CompressionStatus::Failed,
+                    original_bytes_size: get_file_bytes_size(&path.to_string_lossy(), None)
+                        .unwrap_or(0),
                     error_message: Some(e.to_string()),
                     compress_rate: 0.0,
                     cost_time: 0,
                     original_temp_path: "".to_string(),
+                    job_id: context.job_id.clone(),
+                    backup_id: None,
                 },
             };
 
@@ -244,7 +478,12 @@ fn process_compression_tasks<R: Runtime>(
 
             result
         })
-        .collect()
+        .collect();
+
+    context.record_results(&results);
+    context.finish();
+
+    results
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -280,50 +519,112 @@ pub async fn ipc_is_apng(path: String) -> Result<bool, String> {
     Ok(is_apng)
 }
 
+/// 将前端传入的拖入根目录转换为 `source_roots`
+pub fn source_roots(roots: Option<Vec<String>>) -> Vec<PathBuf> {
+    roots
+        .unwrap_or_default()
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+}
+
 #[tauri::command]
 pub async fn ipc_compress_images<R: Runtime>(
     app: AppHandle<R>,
     webview_window: tauri::WebviewWindow,
     paths: Vec<String>,
-) -> Result<(), String> {
-    let compression_options = create_compression_options_from_store(&app)?;
+    roots: Option<Vec<String>>,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    let mut compression_options = create_compression_options_from_store(&app)?;
+    compression_options.source_roots = source_roots(roots);
 
     let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
 
     let options_arc = Arc::new(compression_options);
+    let context = CompressionContext::from_app(&app, job_id);
 
-    let results = process_compression_tasks(&app, &webview_window, paths, options_arc);
+    let results = process_compression_tasks(&webview_window, paths, &context, options_arc);
 
     webview_window
         .emit("compression-completed", serde_json::json!(results))
         .unwrap();
 
-    Ok(())
+    Ok(context.job_id)
 }
 
 #[tauri::command]
 pub async fn ipc_compress_single_image<R: Runtime>(
     app: AppHandle<R>,
     path: String,
+    roots: Option<Vec<String>>,
+    job_id: Option<String>,
 ) -> Result<CompressionResult, String> {
-    let compression_options = create_compression_options_from_store(&app)?;
+    let mut compression_options = create_compression_options_from_store(&app)?;
+    compression_options.source_roots = source_roots(roots);
     let path: PathBuf = PathBuf::from(path);
-    let temp_dir = match app.path().app_cache_dir() {
-        Ok(path) => {
-            let temp_dir = path.join("picsharp_temp");
-            if temp_dir.exists() {
-                temp_dir
-            } else {
-                fs::create_dir_all(temp_dir.clone()).unwrap();
-                temp_dir
-            }
-        }
-        Err(e) => {
-            error!("Failed to get temp dir: {}", e);
-            PathBuf::new()
-        }
-    };
-    let results =
-        compress_single_image(&path, &temp_dir, &compression_options).map_err(|e| e.to_string())?;
-    Ok(results)
+    let context = CompressionContext::from_app(&app, job_id);
+    let result =
+        compress_single_image(&path, &context, &compression_options).map_err(|e| e.to_string());
+    if let Ok(result) = &result {
+        context.record_results(std::slice::from_ref(result));
+    }
+    context.finish();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_file;
+    use image::{ImageFormat, Rgba, RgbaImage};
+    use std::io::Cursor;
+
+    // 只有几种颜色的 RGBA 图片，无损压缩转为调色板后一定变小
+    fn png_data() -> Vec<u8> {
+        let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+        let image = RgbaImage::from_fn(128, 128, |x, y| Rgba(colors[((x / 8 + y) % 3) as usize]));
+        let mut data = Cursor::new(Vec::new());
+        image.write_to(&mut data, ImageFormat::Png).unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn skips_unchanged_source_when_saving_as_new_file() {
+        let input_path = temp_file("png", &png_data());
+        let cache_path = temp_file("json", b"{}");
+        let context = CompressionContext {
+            cache: Some(Arc::new(OptimizedCache::load(cache_path.clone()))),
+            ..CompressionContext::default()
+        };
+        let options = CompressionOptions {
+            output_mode: CompressionOutputMode::SaveAsNewFile,
+            output_collision_policy: CollisionPolicy::AutoNumber,
+            quality_mode: QualityMode::Lossless,
+            ..CompressionOptions::default()
+        };
+
+        let first = compress_single_image(&input_path, &context, &options).unwrap();
+        assert!(matches!(first.status, CompressionStatus::Success));
+        assert_ne!(first.output_path, first.input_path);
+
+        let second = compress_single_image(&input_path, &context, &options).unwrap();
+        assert!(matches!(
+            second.status,
+            CompressionStatus::Skipped(SkipReason::AlreadyOptimized)
+        ));
+        assert!(!numbered_output_exists(&first.output_path));
+
+        let _ = fs::remove_file(&first.output_path);
+        let _ = fs::remove_file(input_path);
+        let _ = fs::remove_file(cache_path);
+    }
+
+    fn numbered_output_exists(output_path: &str) -> bool {
+        let output_path = Path::new(output_path);
+        let stem = output_path.file_stem().unwrap().to_string_lossy();
+        output_path
+            .with_file_name(format!("{}_1.png", stem))
+            .exists()
+    }
 }
@@ -1,12 +1,9 @@
-use crate::image_processor::common::CompressionError;
 use crate::image_processor::common::QualityMode;
-use image;
-use image::ImageReader;
+use crate::image_processor::common::{open_image, CompressionError};
 use imagequant::{self, Attributes, RGBA};
 use oxipng::{optimize_from_memory, Options as OxiOptions};
 use png::{BitDepth, ColorType, Encoder, FilterType};
 use std::fs;
-use std::io::BufWriter;
 use std::path::Path;
 
 pub fn is_apng(input_path: &Path) -> bool {
@@ -37,10 +34,7 @@ pub fn is_apng(input_path: &Path) -> bool {
     false
 }
 
-pub fn lossless_compress_png(
-    input_path: &Path,
-    output_path: &Path,
-) -> Result<(), CompressionError> {
+pub fn lossless_compress_png(input_path: &Path) -> Result<Vec<u8>, CompressionError> {
     let file_data = fs::read(input_path).map_err(|e| CompressionError::Io(e))?;
 
     let mut options = OxiOptions::from_preset(2);
@@ -50,21 +44,11 @@ pub fn lossless_compress_png(
     let optimized_data = optimize_from_memory(&file_data, &options)
         .map_err(|e| CompressionError::ImageProcessing(format!("Oxipng error: {}", e)))?;
 
-    fs::write(output_path, optimized_data).map_err(|e| CompressionError::Io(e))?;
-
-    Ok(())
+    Ok(optimized_data)
 }
 
-pub fn lossy_compress_png(
-    input_path: &Path,
-    output_path: &Path,
-    level: u8,
-) -> Result<(), CompressionError> {
-    let img = ImageReader::open(input_path)
-        .map_err(|e| CompressionError::Io(e))?
-        .decode()
-        .map_err(|e| CompressionError::ImageProcessing(format!("Image decode error: {}", e)))?
-        .to_rgba8();
+pub fn lossy_compress_png(input_path: &Path, level: u8) -> Result<Vec<u8>, CompressionError> {
+    let img = open_image(input_path)?.to_rgba8();
 
     let width = img.width() as usize;
     let height = img.height() as usize;
@@ -157,8 +141,7 @@ pub fn lossy_compress_png(
         output_pixels[i * 4 + 3] = color.a;
     }
 
-    let file = std::fs::File::create(output_path).map_err(|e| CompressionError::Io(e))?;
-    let buf_writer = BufWriter::new(file);
+    let mut buffer = Vec::new();
 
     let filter_type = match level {
         0..=3 => FilterType::NoFilter,
@@ -166,7 +149,7 @@ pub fn lossy_compress_png(
         _ => FilterType::Paeth,
     };
 
-    let mut encoder = Encoder::new(buf_writer, width as u32, height as u32);
+    let mut encoder = Encoder::new(&mut buffer, width as u32, height as u32);
     encoder.set_color(ColorType::Rgba);
     encoder.set_depth(BitDepth::Eight);
     encoder.set_filter(FilterType::Paeth);
@@ -180,15 +163,19 @@ pub fn lossy_compress_png(
         .write_image_data(&output_pixels)
         .map_err(|e| CompressionError::ImageProcessing(format!("PNG data write error: {}", e)))?;
 
-    Ok(())
+    writer
+        .finish()
+        .map_err(|e| CompressionError::ImageProcessing(format!("PNG finish error: {}", e)))?;
+
+    Ok(buffer)
 }
 
-pub fn compress_png(
+/// 压缩PNG并返回编码后的数据，不写入磁盘
+pub fn encode_png(
     input_path: &Path,
-    output_path: &Path,
     level: u8,
     mode: Option<QualityMode>,
-) -> Result<(), CompressionError> {
+) -> Result<Vec<u8>, CompressionError> {
     if is_apng(input_path) {
         return Err(CompressionError::UnsupportedFormat("APNG".to_string()));
     }
@@ -196,8 +183,19 @@ pub fn compress_png(
     log::info!("compress_png:  {:?} {:?}", level, mode);
 
     match mode {
-        Some(QualityMode::Lossless) => lossless_compress_png(input_path, output_path),
-        Some(QualityMode::Lossy) => lossy_compress_png(input_path, output_path, level),
-        None => lossy_compress_png(input_path, output_path, level),
+        Some(QualityMode::Lossless) => lossless_compress_png(input_path),
+        Some(QualityMode::Lossy) => lossy_compress_png(input_path, level),
+        None => lossy_compress_png(input_path, level),
     }
 }
+
+pub fn compress_png(
+    input_path: &Path,
+    output_path: &Path,
+    level: u8,
+    mode: Option<QualityMode>,
+) -> Result<(), CompressionError> {
+    let data = encode_png(input_path, level, mode)?;
+    fs::write(output_path, data).map_err(|e| CompressionError::Io(e))?;
+    Ok(())
+}
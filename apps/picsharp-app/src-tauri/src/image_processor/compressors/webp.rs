@@ -1,6 +1,6 @@
 use crate::image_processor::common::QualityMode;
 use crate::image_processor::common::{
-    has_transparency, is_likely_screenshot, is_webp_animation, might_contain_text,
+    has_transparency, is_likely_screenshot, is_webp_animation, might_contain_text, open_image,
     preprocess_image, CompressionError,
 };
 use image;
@@ -10,12 +10,8 @@ use std::path::Path;
 use webp::Encoder;
 use webp_animation::prelude::*;
 
-pub fn compress_webp_lossless(
-    input_path: &Path,
-    output_path: &Path,
-) -> Result<(), CompressionError> {
-    let img =
-        image::open(input_path).map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
+pub fn encode_webp_lossless(input_path: &Path) -> Result<Vec<u8>, CompressionError> {
+    let img = open_image(input_path)?;
     let rgba_image = img.to_rgba8();
     let width = img.width();
     let height = img.height();
@@ -23,7 +19,16 @@ pub fn compress_webp_lossless(
     let encoder = Encoder::from_rgba(&rgba_image, width, height);
     let webp_data = encoder.encode_lossless();
 
-    std::fs::write(output_path, webp_data.to_vec())
+    Ok(webp_data.to_vec())
+}
+
+pub fn compress_webp_lossless(
+    input_path: &Path,
+    output_path: &Path,
+) -> Result<(), CompressionError> {
+    let webp_data = encode_webp_lossless(input_path)?;
+
+    std::fs::write(output_path, webp_data)
         .map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
 
     Ok(())
@@ -34,8 +39,7 @@ pub fn compress_webp_adaptive(
     output_path: &Path,
     quality_level: u8,
 ) -> Result<(), CompressionError> {
-    let img =
-        image::open(input_path).map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
+    let img = open_image(input_path)?;
 
     let _has_transparency = has_transparency(&img);
     let is_screenshot = is_likely_screenshot(&img);
@@ -48,13 +52,11 @@ pub fn compress_webp_adaptive(
     }
 }
 
-pub fn compress_webp_with_preprocessing(
+pub fn encode_webp_with_preprocessing(
     input_path: &Path,
-    output_path: &Path,
     quality_level: u8,
-) -> Result<(), CompressionError> {
-    let img =
-        image::open(input_path).map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
+) -> Result<Vec<u8>, CompressionError> {
+    let img = open_image(input_path)?;
 
     let quality = match quality_level {
         6 => 10,
@@ -74,7 +76,17 @@ pub fn compress_webp_with_preprocessing(
     let encoder = Encoder::from_rgba(&rgba_image, width, height);
     let webp_data = encoder.encode(quality as f32);
 
-    std::fs::write(output_path, webp_data.to_vec())
+    Ok(webp_data.to_vec())
+}
+
+pub fn compress_webp_with_preprocessing(
+    input_path: &Path,
+    output_path: &Path,
+    quality_level: u8,
+) -> Result<(), CompressionError> {
+    let webp_data = encode_webp_with_preprocessing(input_path, quality_level)?;
+
+    std::fs::write(output_path, webp_data)
         .map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
 
     Ok(())
@@ -322,22 +334,34 @@ pub fn compress_webp_animation(
     Ok(())
 }
 
-pub fn compress_webp(
+/// 压缩WebP并返回编码后的数据，不写入磁盘
+pub fn encode_webp(
     input_path: &Path,
-    output_path: &Path,
     quality_level: u8,
     mode: Option<QualityMode>,
-) -> Result<(), CompressionError> {
+) -> Result<Vec<u8>, CompressionError> {
     if is_webp_animation(input_path).map_err(CompressionError::Io)? {
         return Err(CompressionError::UnsupportedFormat(
             "Animated WebP".to_string(),
         ));
     }
     match mode {
-        Some(QualityMode::Lossless) => compress_webp_lossless(input_path, output_path),
-        Some(QualityMode::Lossy) => {
-            compress_webp_with_preprocessing(input_path, output_path, quality_level)
-        }
-        None => compress_webp_with_preprocessing(input_path, output_path, quality_level),
+        Some(QualityMode::Lossless) => encode_webp_lossless(input_path),
+        Some(QualityMode::Lossy) => encode_webp_with_preprocessing(input_path, quality_level),
+        None => encode_webp_with_preprocessing(input_path, quality_level),
     }
 }
+
+pub fn compress_webp(
+    input_path: &Path,
+    output_path: &Path,
+    quality_level: u8,
+    mode: Option<QualityMode>,
+) -> Result<(), CompressionError> {
+    let webp_data = encode_webp(input_path, quality_level, mode)?;
+
+    std::fs::write(output_path, webp_data)
+        .map_err(|e| CompressionError::ImageProcessing(e.to_string()))?;
+
+    Ok(())
+}
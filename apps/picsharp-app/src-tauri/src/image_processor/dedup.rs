@@ -0,0 +1,332 @@
+use crate::file::{get_file_hash, parse_paths, FileInfo};
+use crate::image_processor::common::open_image;
+use crate::scan::ScanOptions;
+use image::imageops::FilterType;
+use log::warn;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// dHash 汉明距离小于等于该值时认为两张图片相似（共 64 位）
+pub const DEFAULT_SIMILAR_THRESHOLD: u32 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DedupOptions {
+    // 是否查找视觉相似的图片，关闭时只查找字节完全相同的文件
+    pub similar: bool,
+    pub similar_threshold: u32,
+}
+
+impl Default for DedupOptions {
+    fn default() -> Self {
+        Self {
+            similar: true,
+            similar_threshold: DEFAULT_SIMILAR_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuplicateKind {
+    // 字节完全相同
+    #[serde(rename = "identical")]
+    Identical,
+    // 视觉相似
+    #[serde(rename = "similar")]
+    Similar,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateFile {
+    pub path: PathBuf,
+    pub bytes_size: u64,
+    pub width: u32,
+    pub height: u32,
+    // 与建议保留文件的 dHash 汉明距离
+    pub distance: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    pub files: Vec<DuplicateFile>,
+    // 建议保留的文件
+    pub keep: PathBuf,
+    pub keep_reason: String,
+    // 删除其余文件可释放的字节数
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupResult {
+    pub groups: Vec<DuplicateGroup>,
+    pub total_count: usize,
+    pub duplicate_count: usize,
+    pub reclaimable_bytes: u64,
+}
+
+struct Candidate<'a> {
+    file: &'a FileInfo,
+    width: u32,
+    height: u32,
+    dhash: Option<u64>,
+}
+
+/// 计算 dHash：缩放为 9x8 灰度图，比较相邻像素的明暗
+pub fn dhash(path: &Path) -> Option<(u64, u32, u32)> {
+    let img = match open_image(path) {
+        Ok(img) => img,
+        Err(e) => {
+            warn!("[dedup] -> Failed to decode <{}>: {}", path.display(), e);
+            return None;
+        }
+    };
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Some((hash, img.width(), img.height()))
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut i = i;
+    while parent[i] != root {
+        let next = parent[i];
+        parent[i] = root;
+        i = next;
+    }
+    root
+}
+
+/// 以汉明距离为度量的 BK 树，查询时只访问距离区间内的子树
+#[derive(Default)]
+struct BkTree {
+    // (dHash, 候选下标, 子节点：到本节点的距离 -> 节点下标)
+    nodes: Vec<(u64, usize, HashMap<u32, usize>)>,
+}
+
+impl BkTree {
+    fn insert(&mut self, hash: u64, index: usize) {
+        let new_node = self.nodes.len();
+        if new_node == 0 {
+            self.nodes.push((hash, index, HashMap::new()));
+            return;
+        }
+        let mut node = 0;
+        loop {
+            let distance = (self.nodes[node].0 ^ hash).count_ones();
+            match self.nodes[node].2.get(&distance) {
+                Some(&child) => node = child,
+                None => {
+                    self.nodes[node].2.insert(distance, new_node);
+                    self.nodes.push((hash, index, HashMap::new()));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 返回距离小于等于 `threshold` 的候选下标
+    fn find_within(&self, hash: u64, threshold: u32) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(node) = stack.pop() {
+            let (node_hash, index, children) = &self.nodes[node];
+            let distance = (node_hash ^ hash).count_ones();
+            if distance <= threshold {
+                found.push(*index);
+            }
+            let range = distance.saturating_sub(threshold)..=distance + threshold;
+            stack.extend(
+                children
+                    .iter()
+                    .filter(|(d, _)| range.contains(d))
+                    .map(|(_, &child)| child),
+            );
+        }
+        found
+    }
+}
+
+fn path_depth(path: &Path) -> usize {
+    path.components().count()
+}
+
+/// 字节相同的文件保留路径最短的一份，相似图片保留分辨率最高、体积最大（画质最好）的一份
+fn build_group(kind: DuplicateKind, mut members: Vec<&Candidate>) -> DuplicateGroup {
+    match kind {
+        DuplicateKind::Identical => members.sort_by(|a, b| {
+            path_depth(&a.file.path)
+                .cmp(&path_depth(&b.file.path))
+                .then_with(|| a.file.path.cmp(&b.file.path))
+        }),
+        DuplicateKind::Similar => members.sort_by(|a, b| {
+            (b.width as u64 * b.height as u64)
+                .cmp(&(a.width as u64 * a.height as u64))
+                .then_with(|| b.file.bytes_size.cmp(&a.file.bytes_size))
+                .then_with(|| a.file.path.cmp(&b.file.path))
+        }),
+    }
+    // 排序后第一个即为建议保留的文件
+    let keep = members[0];
+    let keep_reason = match kind {
+        DuplicateKind::Identical => "shortest path among identical copies",
+        DuplicateKind::Similar => "highest resolution and largest file among similar images",
+    };
+    let files: Vec<DuplicateFile> = members
+        .iter()
+        .map(|candidate| DuplicateFile {
+            path: candidate.file.path.clone(),
+            bytes_size: candidate.file.bytes_size,
+            width: candidate.width,
+            height: candidate.height,
+            distance: match (candidate.dhash, keep.dhash) {
+                (Some(a), Some(b)) => (a ^ b).count_ones(),
+                _ => 0,
+            },
+        })
+        .collect();
+    DuplicateGroup {
+        kind,
+        reclaimable_bytes: files
+            .iter()
+            .filter(|file| file.path != keep.file.path)
+            .map(|file| file.bytes_size)
+            .sum(),
+        keep: keep.file.path.clone(),
+        keep_reason: keep_reason.to_string(),
+        files,
+    }
+}
+
+/// 先按大小和 sha256 找出字节相同的文件，再对剩余图片按 dHash 聚类找出相似图片
+pub fn find_duplicates(files: &[FileInfo], options: &DedupOptions) -> DedupResult {
+    // 只有大小相同的文件才需要计算哈希
+    let mut by_size: HashMap<u64, Vec<&FileInfo>> = HashMap::new();
+    for file in files {
+        by_size.entry(file.bytes_size).or_default().push(file);
+    }
+    let hashed: Vec<(String, &FileInfo)> = by_size
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .flatten()
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .filter_map(|file| get_file_hash(&file.path).ok().map(|hash| (hash, file)))
+        .collect();
+    let mut by_hash: HashMap<String, Vec<&FileInfo>> = HashMap::new();
+    for (hash, file) in hashed {
+        by_hash.entry(hash).or_default().push(file);
+    }
+
+    let mut groups = Vec::new();
+    // 字节相同的文件只用第一份参与相似度比较
+    let mut identical_paths = HashSet::new();
+    for members in by_hash.into_values().filter(|members| members.len() > 1) {
+        let candidates: Vec<Candidate> = members
+            .iter()
+            .map(|file| {
+                let (width, height) = image::image_dimensions(&file.path).unwrap_or((0, 0));
+                Candidate {
+                    file,
+                    width,
+                    height,
+                    dhash: None,
+                }
+            })
+            .collect();
+        let group = build_group(DuplicateKind::Identical, candidates.iter().collect());
+        identical_paths.extend(
+            group
+                .files
+                .iter()
+                .filter(|file| file.path != group.keep)
+                .map(|file| file.path.clone()),
+        );
+        groups.push(group);
+    }
+
+    if options.similar {
+        let candidates: Vec<Candidate> = files
+            .par_iter()
+            .filter(|file| !identical_paths.contains(&file.path))
+            .filter_map(|file| {
+                dhash(&file.path).map(|(hash, width, height)| Candidate {
+                    file,
+                    width,
+                    height,
+                    dhash: Some(hash),
+                })
+            })
+            .collect();
+
+        // 逐个查询已插入的相似候选再插入自身，避免两两比较
+        let mut parent: Vec<usize> = (0..candidates.len()).collect();
+        let mut tree = BkTree::default();
+        for (j, candidate) in candidates.iter().enumerate() {
+            let Some(hash) = candidate.dhash else {
+                continue;
+            };
+            for i in tree.find_within(hash, options.similar_threshold) {
+                let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+                if root_i != root_j {
+                    parent[root_j] = root_i;
+                }
+            }
+            tree.insert(hash, j);
+        }
+
+        let mut clusters: HashMap<usize, Vec<&Candidate>> = HashMap::new();
+        for (i, candidate) in candidates.iter().enumerate() {
+            let root = find(&mut parent, i);
+            clusters.entry(root).or_default().push(candidate);
+        }
+        groups.extend(
+            clusters
+                .into_values()
+                .filter(|members| members.len() > 1)
+                .map(|members| build_group(DuplicateKind::Similar, members)),
+        );
+    }
+
+    groups.sort_by_key(|group| std::cmp::Reverse(group.reclaimable_bytes));
+
+    DedupResult {
+        total_count: files.len(),
+        duplicate_count: groups.iter().map(|group| group.files.len() - 1).sum(),
+        reclaimable_bytes: groups.iter().map(|group| group.reclaimable_bytes).sum(),
+        groups,
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_find_duplicates(
+    paths: Vec<String>,
+    valid_exts: Vec<String>,
+    scan_options: Option<ScanOptions>,
+    dedup_options: Option<DedupOptions>,
+) -> Result<DedupResult, String> {
+    // 扫描、哈希与聚类都是阻塞操作，放到阻塞线程中执行
+    tokio::task::spawn_blocking(move || {
+        let files = parse_paths(paths, valid_exts, &scan_options.unwrap_or_default());
+        find_duplicates(&files, &dedup_options.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
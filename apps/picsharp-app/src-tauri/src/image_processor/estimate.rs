@@ -0,0 +1,268 @@
+use crate::file::{get_file_bytes_size, get_file_extension, get_file_hash, sniff_file_extension};
+use crate::image_processor::cache::OptimizedCache;
+use crate::image_processor::common::{
+    calculate_compress_rate, CompressionError, CompressionOptions, CompressionStatus, SkipReason,
+};
+use crate::image_processor::compressors::{
+    create_compression_options_from_store, encode_image, LOCAL_SUPPORTED_EXTS,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Runtime};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateItem {
+    pub input_path: String,
+    pub status: CompressionStatus,
+    pub original_bytes_size: u64,
+    pub estimated_bytes_size: u64,
+    pub compress_rate: f64,
+    // 是否真实运行了压缩器，false 表示根据同格式样本推算
+    pub sampled: bool,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateResult {
+    pub items: Vec<EstimateItem>,
+    pub total_count: usize,
+    pub sampled_count: usize,
+    pub original_bytes_size: u64,
+    pub estimated_bytes_size: u64,
+    pub compress_rate: f64,
+}
+
+/// 最终写入磁盘的文件大小，取舍规则与实际压缩相同
+fn resolve_estimated_size(
+    original_size: u64,
+    compressed_size: u64,
+    options: &CompressionOptions,
+) -> u64 {
+    if options.keeps_compressed(original_size, compressed_size) {
+        compressed_size
+    } else {
+        original_size
+    }
+}
+
+fn estimate_compress_rate(original_size: u64, estimated_size: u64) -> f64 {
+    if original_size == 0 {
+        0.0
+    } else {
+        calculate_compress_rate(original_size, estimated_size)
+    }
+}
+
+/// 在内存中运行压缩器，不写入任何文件
+fn estimate_single_image(input_path: &Path, options: &CompressionOptions) -> EstimateItem {
+    let input_path_str = input_path.to_string_lossy().to_string();
+    let original_bytes_size = get_file_bytes_size(&input_path_str, None).unwrap_or(0);
+
+    match encode_image(input_path, options) {
+        Ok(data) => {
+            let estimated_bytes_size =
+                resolve_estimated_size(original_bytes_size, data.len() as u64, options);
+            EstimateItem {
+                input_path: input_path_str,
+                status: CompressionStatus::Success,
+                original_bytes_size,
+                estimated_bytes_size,
+                compress_rate: estimate_compress_rate(original_bytes_size, estimated_bytes_size),
+                sampled: true,
+                error_message: None,
+            }
+        }
+        Err(e) => EstimateItem {
+            input_path: input_path_str,
+            status: CompressionStatus::Failed,
+            original_bytes_size,
+            estimated_bytes_size: original_bytes_size,
+            compress_rate: 0.0,
+            sampled: true,
+            error_message: Some(e.to_string()),
+        },
+    }
+}
+
+/// 均匀抽取样本下标，保证每次运行结果一致
+fn sample_indices(total: usize, sample_size: usize) -> Vec<usize> {
+    if sample_size == 0 || sample_size >= total {
+        return (0..total).collect();
+    }
+    let step = total as f64 / sample_size as f64;
+    (0..sample_size)
+        .map(|i| ((i as f64 * step) as usize).min(total - 1))
+        .collect()
+}
+
+// 与 encode_image 相同，按文件头识别的真实格式，无法识别时按扩展名
+fn image_format(path: &Path) -> String {
+    sniff_file_extension(path).unwrap_or_else(|| get_file_extension(path))
+}
+
+/// 不运行压缩器即可确定结果的文件：已压缩过的跳过，本地引擎不支持的格式失败
+fn estimate_without_encoding(
+    input_path: &Path,
+    format: &str,
+    options: &CompressionOptions,
+    cache: Option<&OptimizedCache>,
+) -> Option<EstimateItem> {
+    let input_path_str = input_path.to_string_lossy().to_string();
+    let original_bytes_size = || get_file_bytes_size(&input_path_str, None).unwrap_or(0);
+    let item = |status, error_message| {
+        let original_bytes_size = original_bytes_size();
+        EstimateItem {
+            input_path: input_path_str.clone(),
+            status,
+            original_bytes_size,
+            estimated_bytes_size: original_bytes_size,
+            compress_rate: 0.0,
+            sampled: false,
+            error_message,
+        }
+    };
+
+    if !LOCAL_SUPPORTED_EXTS.contains(&format) {
+        let error = CompressionError::UnsupportedFormat(format.to_string());
+        return Some(item(CompressionStatus::Failed, Some(error.to_string())));
+    }
+    let cache = cache.filter(|_| options.skip_already_optimized)?;
+    let hash = get_file_hash(input_path).ok()?;
+    cache.contains(&hash, &options.fingerprint()).then(|| {
+        item(
+            CompressionStatus::Skipped(SkipReason::AlreadyOptimized),
+            None,
+        )
+    })
+}
+
+/// 预测压缩后的大小。文件数量超过 `sample_size` 时只压缩样本，
+/// 其余文件按同格式样本的平均体积比推算；每种格式至少抽取一个样本。
+/// 已压缩过的文件（`skip_already_optimized`）与本地引擎不支持的格式不参与抽样。
+pub fn estimate_compression(
+    paths: &[PathBuf],
+    options: &CompressionOptions,
+    cache: Option<&OptimizedCache>,
+    sample_size: Option<usize>,
+) -> EstimateResult {
+    let formats: Vec<String> = paths.par_iter().map(|path| image_format(path)).collect();
+    let mut items: Vec<Option<EstimateItem>> = paths
+        .par_iter()
+        .zip(&formats)
+        .map(|(path, format)| estimate_without_encoding(path, format, options, cache))
+        .collect();
+
+    // 只在需要压缩的文件中抽样，再为没有被抽到的格式补一个样本
+    let candidates: Vec<usize> = (0..paths.len()).filter(|&i| items[i].is_none()).collect();
+    let mut indices: Vec<usize> = sample_indices(candidates.len(), sample_size.unwrap_or(0))
+        .into_iter()
+        .map(|i| candidates[i])
+        .collect();
+    for &i in &candidates {
+        if !indices.iter().any(|&j| formats[j] == formats[i]) {
+            indices.push(i);
+        }
+    }
+
+    let sampled_items: Vec<(usize, EstimateItem)> = indices
+        .par_iter()
+        .map(|&i| (i, estimate_single_image(&paths[i], options)))
+        .collect();
+
+    // 按格式统计样本的体积比 (estimated / original)
+    let mut ratio_by_format: HashMap<&str, (u64, u64)> = HashMap::new();
+    for (i, item) in &sampled_items {
+        if let CompressionStatus::Failed = item.status {
+            continue;
+        }
+        let entry = ratio_by_format
+            .entry(formats[*i].as_str())
+            .or_insert((0, 0));
+        entry.0 += item.original_bytes_size;
+        entry.1 += item.estimated_bytes_size;
+    }
+    let ratio_of = |sizes: &(u64, u64)| -> Option<f64> {
+        if sizes.0 == 0 {
+            None
+        } else {
+            Some(sizes.1 as f64 / sizes.0 as f64)
+        }
+    };
+
+    let sampled_count = sampled_items.len();
+    for (i, item) in sampled_items {
+        items[i] = Some(item);
+    }
+
+    let items: Vec<EstimateItem> = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            item.unwrap_or_else(|| {
+                let input_path_str = paths[i].to_string_lossy().to_string();
+                let original_bytes_size = get_file_bytes_size(&input_path_str, None).unwrap_or(0);
+                // 同格式的样本全部失败时无法推算，不用其他格式的体积比代替
+                let Some(ratio) = ratio_by_format.get(formats[i].as_str()).and_then(ratio_of)
+                else {
+                    return EstimateItem {
+                        input_path: input_path_str,
+                        status: CompressionStatus::Failed,
+                        original_bytes_size,
+                        estimated_bytes_size: original_bytes_size,
+                        compress_rate: 0.0,
+                        sampled: false,
+                        error_message: Some(format!(
+                            "No successful sample for format: {}",
+                            formats[i]
+                        )),
+                    };
+                };
+                let estimated_bytes_size = (original_bytes_size as f64 * ratio).round() as u64;
+                EstimateItem {
+                    input_path: input_path_str,
+                    status: CompressionStatus::Success,
+                    original_bytes_size,
+                    estimated_bytes_size,
+                    compress_rate: estimate_compress_rate(
+                        original_bytes_size,
+                        estimated_bytes_size,
+                    ),
+                    sampled: false,
+                    error_message: None,
+                }
+            })
+        })
+        .collect();
+
+    let original_bytes_size: u64 = items.iter().map(|item| item.original_bytes_size).sum();
+    let estimated_bytes_size: u64 = items.iter().map(|item| item.estimated_bytes_size).sum();
+
+    EstimateResult {
+        total_count: items.len(),
+        sampled_count,
+        original_bytes_size,
+        estimated_bytes_size,
+        compress_rate: estimate_compress_rate(original_bytes_size, estimated_bytes_size),
+        items,
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_estimate_compression<R: Runtime>(
+    app: AppHandle<R>,
+    paths: Vec<String>,
+    sample_size: Option<usize>,
+) -> Result<EstimateResult, String> {
+    let compression_options = create_compression_options_from_store(&app)?;
+    let cache = OptimizedCache::from_app(&app);
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+
+    // 抽样压缩会占满 rayon 线程池，放到阻塞线程中执行
+    tokio::task::spawn_blocking(move || {
+        estimate_compression(&paths, &compression_options, cache.as_deref(), sample_size)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
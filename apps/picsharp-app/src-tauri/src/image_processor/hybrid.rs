@@ -0,0 +1,338 @@
+use crate::file::{
+    get_bytes_hash, get_file_bytes_size, get_file_disk_size, get_file_extension,
+    sniff_file_extension,
+};
+use crate::image_processor::common::{
+    calculate_compress_rate, CompressionError, CompressionOptions, CompressionResult,
+};
+use crate::image_processor::compressors::{
+    already_optimized_result, create_compression_options_from_store, encode_image,
+    record_optimized, source_roots, write_compressed, CompressionContext, LOCAL_SUPPORTED_EXTS,
+};
+use crate::remote::{
+    remote_to_output, RemoteCompressor, RemoteOutput, RemoteProvider, RemoteResult,
+};
+use crate::watcher::mark_self_written;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::Notify;
+
+const DEFAULT_MIN_LOCAL_RATE: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HybridStrategy {
+    // 先本地压缩，压缩率不足或格式不支持时再使用远程服务
+    #[serde(rename = "fallback")]
+    Fallback,
+    // 同时使用两个引擎，保留体积更小的结果
+    #[serde(rename = "race")]
+    Race,
+}
+
+impl std::str::FromStr for HybridStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fallback" => Ok(Self::Fallback),
+            "race" => Ok(Self::Race),
+            _ => Err(format!("Unknown hybrid strategy: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompressionEngine {
+    #[serde(rename = "local")]
+    Local,
+    #[serde(rename = "remote")]
+    Remote,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridOptions {
+    pub strategy: HybridStrategy,
+    // 本地压缩率（百分比）低于该值时改用远程服务
+    pub min_local_rate: f64,
+}
+
+impl Default for HybridOptions {
+    fn default() -> Self {
+        Self {
+            strategy: HybridStrategy::Fallback,
+            min_local_rate: DEFAULT_MIN_LOCAL_RATE,
+        }
+    }
+}
+
+impl HybridOptions {
+    pub fn from_store<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let mut options = Self::default();
+        let Ok(store) = app.store("settings.json") else {
+            return options;
+        };
+        if let Some(strategy) = store
+            .get("compression_hybrid_strategy")
+            .and_then(|v| v.as_str().and_then(|s| s.parse().ok()))
+        {
+            options.strategy = strategy;
+        }
+        if let Some(rate) = store
+            .get("compression_hybrid_min_rate")
+            .and_then(|v| v.as_f64())
+        {
+            options.min_local_rate = rate;
+        }
+        options
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridResult {
+    #[serde(flatten)]
+    pub result: CompressionResult,
+    // 最终采用的引擎
+    pub engine: CompressionEngine,
+    // 采用远程结果时的服务名称
+    pub remote_provider: Option<String>,
+    // 本地引擎的压缩率，不支持该格式或压缩失败时为空
+    pub local_compress_rate: Option<f64>,
+    // 远程服务未被采用时的错误信息
+    pub remote_error: Option<String>,
+}
+
+// 在阻塞线程中编码，避免占用异步运行时
+async fn encode_local(input_path: &Path, options: &CompressionOptions) -> Result<Vec<u8>, String> {
+    let input_path = input_path.to_path_buf();
+    let options = options.clone();
+    tokio::task::spawn_blocking(move || encode_image(&input_path, &options))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 竞速模式下本地编码可能仍在读取原图，远程结果写入临时文件后等待本地编码结束，
+/// 再由 `remote_to_output` 备份原图并移动到输出路径（覆盖模式下即原图）
+struct AfterLocalEncode<'a, C> {
+    inner: &'a C,
+    local_done: &'a Notify,
+}
+
+impl<C: RemoteCompressor + Sync> RemoteCompressor for AfterLocalEncode<'_, C> {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    fn supports(&self, ext: &str) -> bool {
+        self.inner.supports(ext)
+    }
+
+    async fn compress(
+        &self,
+        input_path: &Path,
+        mime: &str,
+        temp_path: &Path,
+    ) -> Result<RemoteOutput, String> {
+        let output = self.inner.compress(input_path, mime, temp_path).await;
+        self.local_done.notified().await;
+        output
+    }
+}
+
+// 远程服务已写入输出文件且备份过原图，本地结果更小时直接覆盖它
+fn replace_with_local(
+    remote: RemoteResult,
+    data: &[u8],
+    context: &CompressionContext,
+    options: &CompressionOptions,
+    start: Instant,
+) -> Result<CompressionResult, String> {
+    let output_path = PathBuf::from(&remote.output_path);
+    fs::write(&output_path, data).map_err(|e| CompressionError::Io(e).to_string())?;
+    mark_self_written(&output_path);
+    let input_path = Path::new(&remote.input_path);
+    record_optimized(
+        context,
+        options,
+        input_path,
+        &output_path,
+        &get_bytes_hash(data),
+    );
+
+    let compressed_bytes_size = data.len() as u64;
+    Ok(CompressionResult {
+        compressed_bytes_size,
+        compressed_disk_size: get_file_disk_size(&remote.output_path, None).unwrap_or(0),
+        compress_rate: calculate_compress_rate(remote.original_bytes_size, compressed_bytes_size),
+        ..remote.into_compression_result(start.elapsed().as_millis() as u64)
+    })
+}
+
+/// 混合模式压缩：按策略组合本地引擎与远程服务，并记录最终采用的引擎
+pub async fn compress_hybrid<R: Runtime>(
+    app: &AppHandle<R>,
+    input_path: &Path,
+    context: &CompressionContext,
+    options: &CompressionOptions,
+    hybrid: &HybridOptions,
+) -> Result<HybridResult, String> {
+    let start = Instant::now();
+    let input_path_str = input_path.to_string_lossy().to_string();
+
+    if !input_path.exists() {
+        return Err(CompressionError::FileNotFound(input_path_str).to_string());
+    }
+
+    if let Some(result) = already_optimized_result(input_path, context, options, start) {
+        return Ok(HybridResult {
+            result,
+            engine: CompressionEngine::Local,
+            remote_provider: None,
+            local_compress_rate: None,
+            remote_error: None,
+        });
+    }
+
+    let ext = sniff_file_extension(input_path).unwrap_or_else(|| get_file_extension(input_path));
+    let remote = match RemoteProvider::from_store(app) {
+        Ok(remote) => Some(remote),
+        Err(e) => {
+            warn!("[hybrid] -> Remote provider unavailable: {}", e);
+            None
+        }
+    };
+    let local_supported = LOCAL_SUPPORTED_EXTS.contains(&ext.as_str());
+    let remote = remote.filter(|remote| remote.supports(&ext));
+    if !local_supported && remote.is_none() {
+        return Err(CompressionError::UnsupportedFormat(ext).to_string());
+    }
+
+    let original_bytes_size = get_file_bytes_size(&input_path_str, None).unwrap_or(0);
+    let local_rate =
+        |data: &Vec<u8>| calculate_compress_rate(original_bytes_size, data.len() as u64);
+
+    let (local, remote_result) = match &remote {
+        Some(remote) if hybrid.strategy == HybridStrategy::Race && local_supported => {
+            let local_done = Notify::new();
+            let remote = AfterLocalEncode {
+                inner: remote,
+                local_done: &local_done,
+            };
+            let (local, remote_result) = tokio::join!(
+                async {
+                    let local = encode_local(input_path, options).await;
+                    // 没有等待者时保留通知，远程结果稍后到达也能立即继续
+                    local_done.notify_one();
+                    local
+                },
+                remote_to_output(&remote, app, &input_path_str, &context.job_id, options)
+            );
+            (local, Some(remote_result))
+        }
+        _ => {
+            let local = if local_supported {
+                encode_local(input_path, options).await
+            } else {
+                Err(CompressionError::UnsupportedFormat(ext.clone()).to_string())
+            };
+            let need_remote = match &local {
+                Ok(data) => local_rate(data) < hybrid.min_local_rate,
+                Err(_) => true,
+            };
+            let remote_result = match &remote {
+                Some(remote) if need_remote => Some(
+                    remote_to_output(remote, app, &input_path_str, &context.job_id, options).await,
+                ),
+                _ => None,
+            };
+            (local, remote_result)
+        }
+    };
+
+    let local_compress_rate = local.as_ref().ok().map(local_rate);
+
+    match (local, remote_result) {
+        // 输出文件已存在且冲突策略为跳过，本地引擎同样会跳过
+        (_, Some(Ok(remote))) if remote.is_skipped() => Ok(HybridResult {
+            result: remote.into_compression_result(start.elapsed().as_millis() as u64),
+            engine: CompressionEngine::Local,
+            remote_provider: None,
+            local_compress_rate,
+            remote_error: None,
+        }),
+        // 缩放或格式转换后的结果与本地结果不可比较，总是采用远程结果。
+        // 本地结果同样需要达到压缩率阈值，否则远程已保留的原图即为最终结果
+        (Ok(data), Some(Ok(remote)))
+            if !remote.transformed
+                && (data.len() as u64) < remote.compressed_bytes_size
+                && options.keeps_compressed(remote.original_bytes_size, data.len() as u64) =>
+        {
+            info!("[hybrid] -> Local result is smaller: {}", input_path_str);
+            Ok(HybridResult {
+                result: replace_with_local(remote, &data, context, options, start)?,
+                engine: CompressionEngine::Local,
+                remote_provider: None,
+                local_compress_rate,
+                remote_error: None,
+            })
+        }
+        (_, Some(Ok(remote))) => Ok(HybridResult {
+            remote_provider: Some(remote.provider.clone()),
+            result: remote.into_compression_result(start.elapsed().as_millis() as u64),
+            engine: CompressionEngine::Remote,
+            local_compress_rate,
+            remote_error: None,
+        }),
+        (Ok(data), remote_result) => {
+            let remote_error = remote_result.and_then(|r| r.err());
+            if let Some(e) = &remote_error {
+                warn!(
+                    "[hybrid] -> Remote compression failed, using local result: {}",
+                    e
+                );
+            }
+            Ok(HybridResult {
+                result: write_compressed(input_path, context, options, start, || Ok(data))?,
+                engine: CompressionEngine::Local,
+                remote_provider: None,
+                local_compress_rate,
+                remote_error,
+            })
+        }
+        (Err(local_error), Some(Err(remote_error))) => {
+            Err(format!("Local: {}; Remote: {}", local_error, remote_error))
+        }
+        (Err(local_error), None) => Err(local_error),
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_compress_hybrid<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    roots: Option<Vec<String>>,
+    job_id: Option<String>,
+) -> Result<HybridResult, String> {
+    let mut compression_options = create_compression_options_from_store(&app)?;
+    compression_options.source_roots = source_roots(roots);
+    let hybrid_options = HybridOptions::from_store(&app);
+    let context = CompressionContext::from_app(&app, job_id);
+    let result = compress_hybrid(
+        &app,
+        Path::new(&path),
+        &context,
+        &compression_options,
+        &hybrid_options,
+    )
+    .await;
+    if let Ok(result) = &result {
+        context.record_results(std::slice::from_ref(&result.result));
+    }
+    context.finish();
+    result
+}
@@ -0,0 +1,199 @@
+use crate::image_processor::common::is_webp_animation;
+use crate::image_processor::compressors::png::is_apng;
+use image::{ImageDecoder, ImageReader};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    // 每个通道的位深
+    pub bit_depth: u8,
+    // 文件中原始的颜色类型，如 Rgba8、L16
+    pub color_type: String,
+    pub has_alpha: bool,
+    pub is_animated: bool,
+    pub frame_count: u32,
+    pub has_icc_profile: bool,
+    pub has_exif: bool,
+}
+
+fn read_u32_be(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u32_le(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+// 读取 APNG acTL 块中的帧数，遇到 IDAT 前未找到时视为静态图
+fn apng_frame_count(path: &Path) -> io::Result<u32> {
+    let mut reader = BufReader::new(File::open(path)?);
+    reader.seek(SeekFrom::Start(8))?;
+    loop {
+        let length = read_u32_be(&mut reader)?;
+        let mut chunk_type = [0u8; 4];
+        reader.read_exact(&mut chunk_type)?;
+        match &chunk_type {
+            b"acTL" => return read_u32_be(&mut reader),
+            b"IDAT" | b"IEND" => return Ok(1),
+            _ => {
+                reader.seek(SeekFrom::Current(length as i64 + 4))?;
+            }
+        }
+    }
+}
+
+// 统计 WebP 中 ANMF 块的数量，只读取块头
+fn webp_frame_count(path: &Path) -> io::Result<u32> {
+    count_webp_frames(&mut BufReader::new(File::open(path)?))
+}
+
+// 块大小来自文件内容，按 u64 计算补齐后的长度，截断的块头视为结束
+fn count_webp_frames(reader: &mut (impl Read + Seek)) -> io::Result<u32> {
+    reader.seek(SeekFrom::Start(12))?;
+    let mut frames = 0;
+    loop {
+        let mut chunk_type = [0u8; 4];
+        if reader.read_exact(&mut chunk_type).is_err() {
+            break;
+        }
+        let Ok(size) = read_u32_le(reader) else {
+            break;
+        };
+        if &chunk_type == b"ANMF" {
+            frames += 1;
+        }
+        let padded = u64::from(size) + u64::from(size & 1);
+        reader.seek(SeekFrom::Current(padded as i64))?;
+    }
+    Ok(frames.max(1))
+}
+
+// 跳过 GIF 的数据子块序列
+fn skip_gif_sub_blocks(reader: &mut impl Read) -> io::Result<()> {
+    loop {
+        let mut size = [0u8; 1];
+        reader.read_exact(&mut size)?;
+        if size[0] == 0 {
+            return Ok(());
+        }
+        io::copy(&mut reader.take(size[0] as u64), &mut io::sink())?;
+    }
+}
+
+// 统计 GIF 中图像描述符的数量，跳过所有像素数据
+fn gif_frame_count(path: &Path) -> io::Result<u32> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut header = [0u8; 13];
+    reader.read_exact(&mut header)?;
+    if header[10] & 0x80 != 0 {
+        let table_size = 3 * (1u64 << ((header[10] & 0x07) + 1));
+        io::copy(&mut (&mut reader).take(table_size), &mut io::sink())?;
+    }
+    let mut frames = 0;
+    loop {
+        let mut introducer = [0u8; 1];
+        if reader.read_exact(&mut introducer).is_err() {
+            break;
+        }
+        match introducer[0] {
+            // 图像描述符
+            0x2C => {
+                frames += 1;
+                let mut descriptor = [0u8; 9];
+                reader.read_exact(&mut descriptor)?;
+                if descriptor[8] & 0x80 != 0 {
+                    let table_size = 3 * (1u64 << ((descriptor[8] & 0x07) + 1));
+                    io::copy(&mut (&mut reader).take(table_size), &mut io::sink())?;
+                }
+                // LZW 最小码长
+                reader.read_exact(&mut [0u8; 1])?;
+                skip_gif_sub_blocks(&mut reader)?;
+            }
+            // 扩展块
+            0x21 => {
+                reader.read_exact(&mut [0u8; 1])?;
+                skip_gif_sub_blocks(&mut reader)?;
+            }
+            _ => break,
+        }
+    }
+    Ok(frames.max(1))
+}
+
+/// 只解析文件头获取图片信息，不解码像素数据
+pub fn read_image_info(path: &Path) -> Option<ImageInfo> {
+    let reader = ImageReader::open(path).ok()?.with_guessed_format().ok()?;
+    let format = reader.format()?;
+    let mut decoder = reader.into_decoder().ok()?;
+
+    let (width, height) = decoder.dimensions();
+    let color_type = decoder.original_color_type();
+    let channel_count = color_type.channel_count().max(1) as u16;
+    let has_icc_profile = matches!(decoder.icc_profile(), Ok(Some(_)));
+    let has_exif = matches!(decoder.exif_metadata(), Ok(Some(_)));
+
+    let frame_count = match format.extensions_str().first().copied() {
+        Some("png") if is_apng(path) => apng_frame_count(path).unwrap_or(1),
+        Some("webp") if is_webp_animation(path).unwrap_or(false) => {
+            webp_frame_count(path).unwrap_or(1)
+        }
+        Some("gif") => gif_frame_count(path).unwrap_or(1),
+        _ => 1,
+    };
+
+    Some(ImageInfo {
+        width,
+        height,
+        bit_depth: (color_type.bits_per_pixel() / channel_count) as u8,
+        color_type: format!("{:?}", color_type),
+        has_alpha: decoder.color_type().has_alpha(),
+        is_animated: frame_count > 1,
+        frame_count,
+        has_icc_profile,
+        has_exif,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn riff(chunks: &[(&[u8; 4], u32, usize)]) -> Vec<u8> {
+        let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+        for (chunk_type, size, payload) in chunks {
+            data.extend_from_slice(*chunk_type);
+            data.extend_from_slice(&size.to_le_bytes());
+            data.extend(std::iter::repeat_n(0u8, *payload));
+        }
+        data
+    }
+
+    #[test]
+    fn counts_anmf_chunks_with_odd_padding() {
+        let data = riff(&[(b"VP8X", 10, 10), (b"ANMF", 3, 4), (b"ANMF", 2, 2)]);
+        assert_eq!(count_webp_frames(&mut Cursor::new(data)).unwrap(), 2);
+    }
+
+    #[test]
+    fn hostile_chunk_size_does_not_overflow() {
+        let data = riff(&[(b"ANMF", u32::MAX, 0)]);
+        assert_eq!(count_webp_frames(&mut Cursor::new(data)).unwrap(), 1);
+    }
+
+    #[test]
+    fn truncated_chunk_header_stops_counting() {
+        let mut data = riff(&[(b"ANMF", 2, 2), (b"ANMF", 2, 2)]);
+        data.extend_from_slice(b"ANMF\x01\x00");
+        assert_eq!(count_webp_frames(&mut Cursor::new(data)).unwrap(), 2);
+    }
+}
@@ -1,2 +1,8 @@
+pub mod audit;
+pub mod cache;
 pub mod common;
 pub mod compressors;
+pub mod dedup;
+pub mod estimate;
+pub mod hybrid;
+pub mod info;
@@ -1,3 +1,4 @@
+use backup::{BackupStore, RetentionPolicy};
 use dotenvy_macro::dotenv;
 use inspect::Inspect;
 use log::{error, info};
@@ -11,13 +12,19 @@ use tauri::{
 use tauri::{AppHandle, Emitter, Listener, Manager, Url};
 use tauri_plugin_aptabase::EventTracker;
 use tauri_plugin_fs::FsExt;
+pub mod backup;
 mod clipboard;
 mod command;
-mod file;
+pub mod file;
 mod file_ext;
-// mod image_processor;
+pub mod history;
+pub mod http;
+pub mod image_processor;
 mod inspect;
-// mod tinify;
+pub mod remote;
+pub mod report;
+pub mod scan;
+mod tinify;
 #[cfg(target_os = "macos")]
 #[macro_use]
 extern crate cocoa;
@@ -27,9 +34,15 @@ extern crate cocoa;
 extern crate objc;
 #[cfg(target_os = "macos")]
 mod macos;
+#[cfg(test)]
+mod test_support;
 mod upload;
+pub mod watcher;
 mod window;
 
+// 与 tauri.conf.json 中的 identifier 保持一致，CLI 据此定位应用目录
+pub const APP_IDENTIFIER: &str = "com.PicSharp.app";
+
 fn init_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
     let app_data_dir = app.path().app_data_dir()?;
     if !app_data_dir.exists() {
@@ -53,12 +66,26 @@ fn init_settings(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn init_temp_dir(app: &AppHandle) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let temp_dir = app.path().app_cache_dir()?.join("picsharp_temp");
+    let temp_dir = app.path().app_cache_dir()?.join(backup::TEMP_DIR_NAME);
+    let backup_store = BackupStore::from_temp_dir(&temp_dir);
     if temp_dir.exists() {
-        fs::remove_dir_all(&temp_dir)?;
+        // 保留原图备份，其余临时文件每次启动时清理
+        for entry in fs::read_dir(&temp_dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path == backup_store.dir() {
+                continue;
+            }
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
     }
     fs::create_dir_all(&temp_dir)?;
 
+    backup_store.prune(&RetentionPolicy::from_store(app))?;
+
     Ok(temp_dir)
 }
 
@@ -85,7 +112,7 @@ fn allow_file_in_scopes(app: &AppHandle, files: Vec<PathBuf>) {
 }
 
 #[cfg(desktop)]
-fn set_window_open_with_files(app: &AppHandle, files: Vec<PathBuf>) {
+fn set_window_open_with_files(app: &AppHandle, mode: &str, files: Vec<PathBuf>) {
     let files = files
         .into_iter()
         .map(|f| {
@@ -95,7 +122,7 @@ fn set_window_open_with_files(app: &AppHandle, files: Vec<PathBuf>) {
         .collect::<Vec<_>>()
         .join(",");
     if let Some(window) = app.get_webview_window("main") {
-        let payload = format!("{{mode: \"ns_compress\", paths: [{}]}}", files);
+        let payload = format!("{{mode: \"{}\", paths: [{}]}}", mode, files);
         info!("[set_window_open_with_files] -> payload: {}", payload);
         let script = format!("window.LAUNCH_PAYLOAD = {};", payload);
         if let Err(e) = window.eval(&script) {
@@ -150,6 +177,19 @@ struct Payload {
     cwd: String,
 }
 
+// 根据启动参数确定前端处理模式，`--watch` 为监听模式，其余为压缩模式
+fn get_launch_mode(argv: &[String]) -> &'static str {
+    if argv
+        .iter()
+        .skip(1)
+        .any(|arg| arg == "--watch" || arg == "-w")
+    {
+        "ns_watch_and_compress"
+    } else {
+        "ns_compress"
+    }
+}
+
 fn get_files_from_argv(argv: Vec<String>) -> Vec<PathBuf> {
     let mut files = Vec::new();
     for (_, maybe_file) in argv.iter().enumerate().skip(1) {
@@ -188,11 +228,12 @@ pub fn run() {
                         error
                     );
                 });
+                let mode = get_launch_mode(&args);
                 let files = get_files_from_argv(args.clone());
                 if !files.is_empty() {
                     allow_file_in_scopes(app, files.clone());
                     app.emit(
-                        "ns_compress",
+                        mode,
                         files
                             .iter()
                             .map(|f| f.to_string_lossy().to_string())
@@ -200,8 +241,8 @@ pub fn run() {
                     )
                     .unwrap_or_else(|error| {
                         error!(
-                            "[Single Instance Emit] -> Failed to emit ns_compress event: {}",
-                            error
+                            "[Single Instance Emit] -> Failed to emit {} event: {}",
+                            mode, error
                         );
                     });
                 }
@@ -232,17 +273,41 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(watcher::WatcherRegistry::default())
+        .manage(tinify::keys::KeyRateLimiter::default())
         .invoke_handler(tauri::generate_handler![
             file::ipc_parse_paths,
+            file::ipc_scan_paths,
+            file::ipc_cancel_scan,
             file::ipc_count_valid_files,
+            file::ipc_fix_file_extensions,
             file::ipc_is_file_in_directory,
             file::ipc_copy_image,
             file::ipc_get_file_name,
             clipboard::ipc_parse_clipboard_images,
-            // tinify::ipc_tinify,
-            // image_processor::compressors::ipc_compress_images,
-            // image_processor::compressors::ipc_compress_single_image,
-            // image_processor::compressors::ipc_is_apng,
+            tinify::ipc_tinify,
+            tinify::batch::ipc_tinify_batch,
+            tinify::batch::ipc_cancel_tinify_batch,
+            remote::ipc_compress_remote,
+            tinify::keys::ipc_tinify_key_status,
+            tinify::keys::ipc_tinify_validate_key,
+            tinify::keys::ipc_tinify_add_key,
+            tinify::keys::ipc_tinify_remove_key,
+            tinify::keys::ipc_tinify_api_keys,
+            tinify::keys::ipc_tinify_key_storage,
+            image_processor::compressors::ipc_compress_images,
+            image_processor::compressors::ipc_compress_single_image,
+            image_processor::hybrid::ipc_compress_hybrid,
+            image_processor::compressors::ipc_is_apng,
+            image_processor::estimate::ipc_estimate_compression,
+            image_processor::dedup::ipc_find_duplicates,
+            image_processor::audit::ipc_audit_images,
+            image_processor::audit::ipc_export_audit_report,
+            report::ipc_export_report,
+            backup::ipc_restore_original,
+            backup::ipc_restore_job,
+            watcher::ipc_watch_start,
+            watcher::ipc_watch_stop,
             command::ipc_open_system_preference_notifications,
             command::ipc_kill_processes_by_name,
             command::ipc_kill_picsharp_sidecar_processes,
@@ -274,18 +339,30 @@ pub fn run() {
                 }
             }
 
+            image_processor::cache::OptimizedCache::init(app.handle());
+            if let Ok(history) = history::JobHistory::from_app(app.handle()) {
+                history.prune_or_log();
+            }
+            app.manage(tinify::secret::TinifyKeyVault::from_app(app.handle()));
+            app.manage(tinify::keys::TinifyKeyManager::from_app(app.handle()));
+            if let Err(e) = tinify::keys::migrate_plaintext_keys(app.handle()) {
+                error!("Failed to migrate TinyPNG API keys: {}", e);
+            }
+
             let inspect = Inspect::new(app.handle().clone())?;
             file_ext::load(inspect);
 
             #[cfg(desktop)]
             {
-                let files = get_files_from_argv(std::env::args().collect());
+                let args: Vec<String> = std::env::args().collect();
+                let mode = get_launch_mode(&args);
+                let files = get_files_from_argv(args);
                 if !files.is_empty() {
                     let app_handle = app.handle().clone();
                     allow_file_in_scopes(&app_handle, files.clone());
                     app.listen("window-ready", move |_| {
                         info!("[Setup] -> Launching with files: {:?}", files);
-                        set_window_open_with_files(&app_handle, files.clone());
+                        set_window_open_with_files(&app_handle, mode, files.clone());
                     });
                 }
             }
@@ -360,7 +437,7 @@ pub fn run() {
                 let app_handler_clone = app.clone();
                 allow_file_in_scopes(app, files.clone());
                 app.listen("window-ready", move |_| {
-                    set_window_open_with_files(&app_handler_clone, files.clone());
+                    set_window_open_with_files(&app_handler_clone, "ns_compress", files.clone());
                 });
             }
         });
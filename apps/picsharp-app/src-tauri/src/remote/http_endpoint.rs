@@ -0,0 +1,353 @@
+use super::{ext_from_content_type, RemoteCompressor, RemoteOutput};
+use crate::http::{send_with_retry, HttpConfig};
+use crate::upload::{download_file, file_to_body, save_response, ProgressReporter};
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+/// 服务响应的解析方式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum HttpEndpointResponse {
+    // 响应体即为压缩后的图片
+    #[serde(rename = "binary")]
+    Binary,
+    // 响应为 JSON，按 JSON Pointer（如 "/output/url"）取出结果图片地址，相对地址基于服务 URL
+    #[serde(rename = "json")]
+    Json {
+        url_pointer: String,
+        #[serde(default)]
+        error_pointer: Option<String>,
+    },
+}
+
+/// 自建压缩服务（如 imgproxy、Squoosh 服务）的配置，对应设置 `compression_remote_http_endpoint`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpEndpointConfig {
+    pub url: String,
+    // POST 或 PUT，请求体为原图
+    pub method: String,
+    // 认证请求头名称与值，值为空时不发送
+    pub auth_header: String,
+    pub auth_value: String,
+    pub response: HttpEndpointResponse,
+    // 服务支持的文件扩展名
+    pub extensions: Vec<String>,
+}
+
+impl Default for HttpEndpointConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            method: "POST".to_string(),
+            auth_header: "Authorization".to_string(),
+            auth_value: String::new(),
+            response: HttpEndpointResponse::Binary,
+            extensions: ["png", "jpg", "jpeg", "webp", "avif"]
+                .iter()
+                .map(|ext| ext.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 通过自定义 HTTP 接口压缩图片，图片不经过第三方服务
+pub struct HttpEndpointCompressor {
+    config: HttpEndpointConfig,
+    url: Url,
+    method: Method,
+    http: HttpConfig,
+    progress: Option<ProgressReporter>,
+}
+
+impl HttpEndpointCompressor {
+    pub fn new(config: HttpEndpointConfig, http: HttpConfig) -> Result<Self, String> {
+        let url = Url::parse(config.url.trim())
+            .map_err(|e| format!("Invalid HTTP endpoint URL '{}': {}", config.url, e))?;
+        let method = match config.method.to_uppercase().as_str() {
+            "POST" => Method::POST,
+            "PUT" => Method::PUT,
+            method => return Err(format!("Unsupported HTTP endpoint method: {}", method)),
+        };
+        Ok(Self {
+            config,
+            url,
+            method,
+            http,
+            progress: None,
+        })
+    }
+
+    /// 上传与下载时通过 progress 发送传输进度
+    pub fn with_progress(mut self, progress: Option<ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn from_store<R: Runtime>(app: &AppHandle<R>) -> Result<Self, String> {
+        let store = app.store("settings.json").map_err(|e| e.to_string())?;
+        let config = match store.get("compression_remote_http_endpoint") {
+            Some(value) => serde_json::from_value(value)
+                .map_err(|e| format!("Invalid HTTP endpoint settings: {}", e))?,
+            None => HttpEndpointConfig::default(),
+        };
+        Self::new(config, HttpConfig::from_store(app))
+    }
+
+    fn auth_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        if !self.config.auth_header.is_empty() && !self.config.auth_value.is_empty() {
+            headers.insert(
+                self.config.auth_header.clone(),
+                self.config.auth_value.clone(),
+            );
+        }
+        headers
+    }
+}
+
+// 按 JSON Pointer 取出字符串或其他值的文本形式
+fn pointer_text(json: &Value, pointer: &str) -> Option<String> {
+    match json.pointer(pointer)? {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+impl RemoteCompressor for HttpEndpointCompressor {
+    fn name(&self) -> &'static str {
+        "http_endpoint"
+    }
+
+    fn supports(&self, ext: &str) -> bool {
+        self.config
+            .extensions
+            .iter()
+            .any(|supported| supported.eq_ignore_ascii_case(ext))
+    }
+
+    async fn compress(
+        &self,
+        input_path: &Path,
+        mime: &str,
+        temp_path: &Path,
+    ) -> Result<RemoteOutput, String> {
+        let client = self.http.client().map_err(|e| e.to_string())?;
+        let headers = self.auth_headers();
+
+        // 每次重试都重新打开文件
+        let response = send_with_retry(&self.http.retry, self.method.is_idempotent(), || async {
+            let file = tokio::fs::File::open(input_path).await?;
+            let file_len = file.metadata().await?.len();
+            let mut request = client
+                .request(self.method.clone(), self.url.clone())
+                .header(reqwest::header::CONTENT_TYPE, mime)
+                .header(reqwest::header::CONTENT_LENGTH, file_len)
+                .body(file_to_body(file, file_len, self.progress.clone()));
+            for (key, value) in &headers {
+                request = request.header(key, value);
+            }
+            Ok::<_, crate::upload::Error>(request)
+        })
+        .await
+        .map_err(|e| format!("Upload failed: {}", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("HTTP endpoint returned {}: {}", status, body));
+        }
+
+        let ext = match &self.config.response {
+            HttpEndpointResponse::Binary => {
+                let ext = ext_from_content_type(response.headers());
+                save_response(
+                    response,
+                    &temp_path.to_string_lossy(),
+                    self.progress.as_ref(),
+                )
+                .await
+                .map_err(|e| format!("Download failed: {}", e))?;
+                ext
+            }
+            HttpEndpointResponse::Json {
+                url_pointer,
+                error_pointer,
+            } => {
+                let json: Value = response
+                    .json()
+                    .await
+                    .map_err(|e| format!("Failed to parse JSON response: {}", e))?;
+                if let Some(message) = error_pointer
+                    .as_deref()
+                    .and_then(|pointer| pointer_text(&json, pointer))
+                {
+                    return Err(format!("HTTP endpoint returned error: {}", message));
+                }
+                let output_url = pointer_text(&json, url_pointer)
+                    .ok_or_else(|| "Cannot get the compressed image URL".to_string())?;
+                let output_url = self
+                    .url
+                    .join(&output_url)
+                    .map_err(|e| format!("Invalid compressed image URL: {}", e))?;
+                // 结果地址可能指向其他域名（如对象存储），只向同源地址发送认证信息
+                let download_headers = if output_url.origin() == self.url.origin() {
+                    headers
+                } else {
+                    HashMap::new()
+                };
+                let response_headers = download_file(
+                    output_url.as_str(),
+                    &temp_path.to_string_lossy(),
+                    download_headers,
+                    None,
+                    &self.http,
+                    self.progress.as_ref(),
+                )
+                .await
+                .map_err(|e| format!("Download failed: {}", e))?;
+                ext_from_content_type(&response_headers)
+            }
+        };
+
+        Ok(RemoteOutput {
+            ext,
+            transformed: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_file, StubResponse, StubServer};
+
+    const AUTH_HEADER: &str = "X-Api-Key";
+    const AUTH_VALUE: &str = "secret";
+
+    fn compressor(url: &str, response: HttpEndpointResponse) -> HttpEndpointCompressor {
+        let config = HttpEndpointConfig {
+            url: url.to_string(),
+            auth_header: AUTH_HEADER.to_string(),
+            auth_value: AUTH_VALUE.to_string(),
+            response,
+            ..HttpEndpointConfig::default()
+        };
+        HttpEndpointCompressor::new(config, HttpConfig::default()).unwrap()
+    }
+
+    fn json_response(url_pointer: &str) -> HttpEndpointResponse {
+        HttpEndpointResponse::Json {
+            url_pointer: url_pointer.to_string(),
+            error_pointer: Some("/error/message".to_string()),
+        }
+    }
+
+    // 上传一个临时文件，返回结果与写入临时输出文件的内容
+    async fn run(compressor: &HttpEndpointCompressor) -> (Result<RemoteOutput, String>, Vec<u8>) {
+        let input_path = temp_file("png", b"original");
+        let temp_path = input_path.with_extension("remote");
+        let output = compressor
+            .compress(&input_path, "image/png", &temp_path)
+            .await;
+        let data = std::fs::read(&temp_path).unwrap_or_default();
+        let _ = std::fs::remove_file(&input_path);
+        let _ = std::fs::remove_file(&temp_path);
+        (output, data)
+    }
+
+    #[test]
+    fn supports_configured_extensions_case_insensitively() {
+        let compressor = compressor("http://127.0.0.1/", HttpEndpointResponse::Binary);
+        assert!(compressor.supports("PNG"));
+        assert!(compressor.supports("jpeg"));
+        assert!(!compressor.supports("gif"));
+    }
+
+    #[tokio::test]
+    async fn binary_response_is_saved_with_content_type_ext() {
+        let server = StubServer::sequence(vec![StubResponse::new(200)
+            .header("Content-Type", "image/webp")
+            .body("compressed")]);
+        let compressor = compressor(
+            &format!("{}/compress", server.url),
+            HttpEndpointResponse::Binary,
+        );
+
+        let (output, data) = run(&compressor).await;
+        assert_eq!(output.unwrap().ext.as_deref(), Some("webp"));
+        assert_eq!(data, b"compressed");
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/compress");
+        assert_eq!(requests[0].header(AUTH_HEADER), Some(AUTH_VALUE));
+        assert_eq!(requests[0].body, b"original");
+    }
+
+    #[tokio::test]
+    async fn json_relative_url_is_joined_and_downloaded_with_auth() {
+        let server = StubServer::start(|request| match request.path.as_str() {
+            "/api/compress" => {
+                StubResponse::new(200).body(r#"{"output":{"url":"../files/out.png"}}"#)
+            }
+            "/files/out.png" => StubResponse::new(200)
+                .header("Content-Type", "image/png")
+                .body("compressed"),
+            _ => StubResponse::new(404),
+        });
+        let compressor = compressor(
+            &format!("{}/api/compress", server.url),
+            json_response("/output/url"),
+        );
+
+        let (output, data) = run(&compressor).await;
+        assert_eq!(output.unwrap().ext.as_deref(), Some("png"));
+        assert_eq!(data, b"compressed");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].method, "GET");
+        assert_eq!(requests[1].header(AUTH_HEADER), Some(AUTH_VALUE));
+    }
+
+    #[tokio::test]
+    async fn json_cross_origin_download_omits_auth() {
+        let storage = StubServer::sequence(vec![StubResponse::new(200)
+            .header("Content-Type", "image/png")
+            .body("compressed")]);
+        let body = format!(r#"{{"result":"{}/out.png"}}"#, storage.url);
+        let server = StubServer::sequence(vec![StubResponse::new(200).body(body)]);
+        let compressor = compressor(&server.url, json_response("/result"));
+
+        let (output, data) = run(&compressor).await;
+        assert!(output.is_ok());
+        assert_eq!(data, b"compressed");
+        assert_eq!(server.requests()[0].header(AUTH_HEADER), Some(AUTH_VALUE));
+        assert_eq!(storage.requests()[0].header(AUTH_HEADER), None);
+    }
+
+    #[tokio::test]
+    async fn json_error_pointer_is_reported() {
+        let server = StubServer::sequence(vec![
+            StubResponse::new(200).body(r#"{"error":{"message":"image too large"}}"#)
+        ]);
+        let compressor = compressor(&server.url, json_response("/output/url"));
+
+        let (output, _) = run(&compressor).await;
+        assert!(output.unwrap_err().contains("image too large"));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn json_without_url_is_an_error() {
+        let server = StubServer::sequence(vec![StubResponse::new(200).body(r#"{"output":{}}"#)]);
+        let compressor = compressor(&server.url, json_response("/output/url"));
+
+        let (output, _) = run(&compressor).await;
+        assert!(output.is_err());
+    }
+}
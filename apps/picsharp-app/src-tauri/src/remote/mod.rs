@@ -0,0 +1,380 @@
+pub mod http_endpoint;
+
+use crate::backup::BackupStore;
+use crate::file::{convert_file_src, get_file_bytes_size, get_file_disk_size, get_file_mime_type};
+use crate::image_processor::common::{
+    calculate_compress_rate, claim_output_path, get_output_path, CompressionOptions,
+    CompressionResult, CompressionStatus, SkipReason,
+};
+use crate::image_processor::compressors::{create_compression_options_from_store, source_roots};
+use crate::tinify::TinifyCompressor;
+use crate::upload::{ProgressPayload, ProgressReporter};
+use crate::watcher::mark_self_written;
+use http_endpoint::HttpEndpointCompressor;
+use image::ImageFormat;
+use log::{error, info};
+use nanoid::nanoid;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+/// 远程服务写入临时文件的压缩结果
+#[derive(Debug, Clone, Default)]
+pub struct RemoteOutput {
+    // 结果图片的扩展名，格式转换后可能与输入不同，为空时沿用输出路径的扩展名
+    pub ext: Option<String>,
+    // 服务端做了缩放或格式转换，即使压缩率不达标也使用其输出
+    pub transformed: bool,
+}
+
+/// 远程压缩服务，TinyPNG 与自建的 HTTP 服务都实现该接口
+pub trait RemoteCompressor {
+    /// 服务名称，记录在压缩结果中
+    fn name(&self) -> &'static str;
+
+    /// 是否支持压缩该扩展名的图片
+    fn supports(&self, ext: &str) -> bool;
+
+    /// 上传 `input_path` 并将压缩结果写入 `temp_path`
+    fn compress(
+        &self,
+        input_path: &Path,
+        mime: &str,
+        temp_path: &Path,
+    ) -> impl Future<Output = Result<RemoteOutput, String>> + Send;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteResult {
+    // 使用的远程服务名称
+    pub provider: String,
+    // 成功，或因输出文件已存在而跳过
+    pub status: CompressionStatus,
+    pub input_path: String,
+    pub original_bytes_size: u64,
+    pub compressed_bytes_size: u64,
+    pub compressed_disk_size: u64,
+    pub compress_rate: f64,
+    pub output_path: String,
+    pub output_path_converted: String,
+    pub original_temp_path: String,
+    pub job_id: String,
+    pub backup_id: Option<String>,
+    // 输出经过缩放或格式转换
+    #[serde(default)]
+    pub transformed: bool,
+}
+
+impl RemoteResult {
+    /// 输出文件已存在且冲突策略为跳过时的结果，与本地引擎一致，不上传文件
+    fn output_exists(provider: &str, input_path: &str, job_id: &str) -> Self {
+        let original_bytes_size = get_file_bytes_size(input_path, None).unwrap_or(0);
+        Self {
+            provider: provider.to_string(),
+            status: CompressionStatus::Skipped(SkipReason::OutputExists),
+            input_path: input_path.to_string(),
+            original_bytes_size,
+            compressed_bytes_size: original_bytes_size,
+            compressed_disk_size: get_file_disk_size(input_path, None).unwrap_or(0),
+            compress_rate: 0.0,
+            output_path: input_path.to_string(),
+            output_path_converted: convert_file_src(input_path).unwrap_or_default(),
+            original_temp_path: "".to_string(),
+            job_id: job_id.to_string(),
+            backup_id: None,
+            transformed: false,
+        }
+    }
+
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.status, CompressionStatus::Skipped(_))
+    }
+
+    /// 转换为与本地引擎一致的结果，用于进度事件与任务记录
+    pub fn into_compression_result(self, cost_time: u64) -> CompressionResult {
+        CompressionResult {
+            input_path: self.input_path,
+            status: self.status,
+            original_bytes_size: self.original_bytes_size,
+            output_path: self.output_path,
+            output_path_converted: self.output_path_converted,
+            compressed_bytes_size: self.compressed_bytes_size,
+            compressed_disk_size: self.compressed_disk_size,
+            cost_time,
+            compress_rate: self.compress_rate,
+            error_message: None,
+            original_temp_path: self.original_temp_path,
+            job_id: self.job_id,
+            backup_id: self.backup_id,
+        }
+    }
+}
+
+/// 根据响应的 Content-Type 得到图片扩展名
+pub fn ext_from_content_type(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| ImageFormat::from_mime_type(v.split(';').next().unwrap_or(v).trim()))
+        .and_then(|format| format.extensions_str().first())
+        .map(|ext| ext.to_string())
+}
+
+/// 按 `compression_remote_provider` 设置选择的远程服务，默认为 TinyPNG
+pub enum RemoteProvider<R: Runtime> {
+    Tinify(TinifyCompressor<R>),
+    HttpEndpoint(Box<HttpEndpointCompressor>),
+}
+
+impl<R: Runtime> RemoteProvider<R> {
+    /// 上传与下载时通过 progress 发送传输进度
+    pub fn with_progress(self, progress: Option<ProgressReporter>) -> Self {
+        match self {
+            Self::Tinify(compressor) => Self::Tinify(compressor.with_progress(progress)),
+            Self::HttpEndpoint(compressor) => {
+                Self::HttpEndpoint(Box::new(compressor.with_progress(progress)))
+            }
+        }
+    }
+
+    pub fn from_store(app: &AppHandle<R>) -> Result<Self, String> {
+        let store = app.store("settings.json").map_err(|e| e.to_string())?;
+        let provider = store
+            .get("compression_remote_provider")
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_default();
+        match provider.as_str() {
+            "http_endpoint" => HttpEndpointCompressor::from_store(app)
+                .map(|compressor| Self::HttpEndpoint(Box::new(compressor))),
+            _ => TinifyCompressor::from_app(app)
+                .map(Self::Tinify)
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+impl<R: Runtime> RemoteCompressor for RemoteProvider<R> {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Tinify(compressor) => compressor.name(),
+            Self::HttpEndpoint(compressor) => compressor.name(),
+        }
+    }
+
+    fn supports(&self, ext: &str) -> bool {
+        match self {
+            Self::Tinify(compressor) => compressor.supports(ext),
+            Self::HttpEndpoint(compressor) => compressor.supports(ext),
+        }
+    }
+
+    async fn compress(
+        &self,
+        input_path: &Path,
+        mime: &str,
+        temp_path: &Path,
+    ) -> Result<RemoteOutput, String> {
+        match self {
+            Self::Tinify(compressor) => compressor.compress(input_path, mime, temp_path).await,
+            Self::HttpEndpoint(compressor) => {
+                compressor.compress(input_path, mime, temp_path).await
+            }
+        }
+    }
+}
+
+/// 使用远程服务压缩图片，压缩率不达标时保留原图
+pub async fn compress_with_remote<R: Runtime, C: RemoteCompressor>(
+    compressor: &C,
+    app: &AppHandle<R>,
+    input_path: &str,
+    output_path: &str,
+    job_id: &str,
+    options: &CompressionOptions,
+) -> Result<RemoteResult, String> {
+    let input_file = Path::new(input_path);
+    let mime = get_file_mime_type(input_file).unwrap_or_default();
+
+    // 转换格式后扩展名由服务端决定，先写入临时文件
+    let temp_path = format!("{}.remote", output_path);
+    let output = match compressor
+        .compress(input_file, &mime, Path::new(&temp_path))
+        .await
+    {
+        Ok(output) => output,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(e);
+        }
+    };
+
+    let original_size = get_file_bytes_size(input_path, None).unwrap_or(0);
+    let compressed_size = get_file_bytes_size(&temp_path, None).unwrap_or(original_size);
+
+    // 与本地引擎按相同规则取舍，设置了缩放或格式转换时总是使用服务端的输出
+    let use_compressed_file =
+        output.transformed || options.keeps_compressed(original_size, compressed_size);
+
+    // 服务端转换了格式时，新扩展名的输出路径同样按冲突策略占用
+    let mut output_path = output_path.to_string();
+    // 持有到文件写入完成
+    let mut _converted_guard = None;
+    if let Some(ext) = output.ext.as_ref().filter(|_| use_compressed_file) {
+        let current = Path::new(&output_path);
+        if ImageFormat::from_path(current).ok() != ImageFormat::from_extension(ext) {
+            let Some(guard) = claim_output_path(input_file, current.with_extension(ext), options)
+            else {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                info!("Skip file because converted output exists: {}", input_path);
+                return Ok(RemoteResult::output_exists(
+                    compressor.name(),
+                    input_path,
+                    job_id,
+                ));
+            };
+            output_path = guard.path().to_string_lossy().to_string();
+            _converted_guard = Some(guard);
+        }
+    }
+
+    // 只备份将被替换的文件，备份失败时不覆盖
+    let replaces_output = use_compressed_file || output_path != input_path;
+    let backup = match BackupStore::from_app(app) {
+        Ok(store) if replaces_output => {
+            match store.backup_replaced(Path::new(&output_path), job_id) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let _ = tokio::fs::remove_file(&temp_path).await;
+                    return Err(format!("Failed to backup original file: {}", e));
+                }
+            }
+        }
+        Ok(_) => None,
+        Err(e) => {
+            error!("Failed to get backup store: {}", e);
+            None
+        }
+    };
+
+    if use_compressed_file {
+        tokio::fs::rename(&temp_path, &output_path)
+            .await
+            .map_err(|e| format!("File operation failed: {}", e))?;
+    } else {
+        let _ = tokio::fs::remove_file(&temp_path).await;
+        if output_path != input_path {
+            // 使用原始文件
+            tokio::fs::copy(input_path, &output_path)
+                .await
+                .map_err(|e| format!("File operation failed: {}", e))?;
+        }
+    }
+    mark_self_written(Path::new(&output_path));
+
+    // 原图未被覆盖时直接指向原图，供界面对比与撤销
+    let original_temp_path = if output_path != input_path {
+        convert_file_src(input_path).unwrap_or_default()
+    } else {
+        backup
+            .as_ref()
+            .and_then(|entry| convert_file_src(&entry.backup_path).ok())
+            .unwrap_or_default()
+    };
+    // 以实际写入的文件为准
+    let compressed_bytes_size = get_file_bytes_size(&output_path, None).unwrap_or(compressed_size);
+
+    Ok(RemoteResult {
+        provider: compressor.name().to_string(),
+        status: CompressionStatus::Success,
+        input_path: input_path.to_string(),
+        original_bytes_size: original_size,
+        compressed_bytes_size,
+        compressed_disk_size: get_file_disk_size(&output_path, None).unwrap_or(0),
+        compress_rate: if compressed_bytes_size < original_size {
+            calculate_compress_rate(original_size, compressed_bytes_size)
+        } else {
+            0.0
+        },
+        output_path_converted: convert_file_src(&output_path).unwrap_or_default(),
+        output_path,
+        original_temp_path,
+        job_id: job_id.to_string(),
+        backup_id: backup.map(|entry| entry.id),
+        transformed: output.transformed,
+    })
+}
+
+/// 按本地引擎的输出路径与冲突策略占用输出文件，再调用远程服务压缩
+pub async fn remote_to_output<R: Runtime, C: RemoteCompressor>(
+    compressor: &C,
+    app: &AppHandle<R>,
+    input_path: &str,
+    job_id: &str,
+    options: &CompressionOptions,
+) -> Result<RemoteResult, String> {
+    let input_file = Path::new(input_path);
+    let Some(output_guard) =
+        claim_output_path(input_file, get_output_path(input_file, options), options)
+    else {
+        info!("Skip file because output exists: {}", input_path);
+        return Ok(RemoteResult::output_exists(
+            compressor.name(),
+            input_path,
+            job_id,
+        ));
+    };
+    let final_output_path = output_guard.path().to_string_lossy().to_string();
+
+    // 确保输出目录存在
+    if let Some(parent) = Path::new(&final_output_path).parent() {
+        if !parent.exists() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Create output directory failed: {}", e))?;
+        }
+    }
+
+    compress_with_remote(
+        compressor,
+        app,
+        input_path,
+        &final_output_path,
+        job_id,
+        options,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn ipc_compress_remote<R: Runtime>(
+    app: AppHandle<R>,
+    input_path: String,
+    roots: Option<Vec<String>>,
+    job_id: Option<String>,
+    request_id: Option<String>,
+    on_progress: Channel<ProgressPayload>,
+) -> Result<RemoteResult, String> {
+    let input_file = Path::new(&input_path);
+    if !input_file.is_file() {
+        return Err(format!("File not found: {}", input_path));
+    }
+
+    let progress = ProgressReporter::new(
+        request_id.unwrap_or_else(|| input_path.clone()),
+        on_progress,
+    );
+    let compressor = RemoteProvider::from_store(&app)?.with_progress(Some(progress));
+    let mut options = create_compression_options_from_store(&app)?;
+    options.source_roots = source_roots(roots);
+    remote_to_output(
+        &compressor,
+        &app,
+        &input_path,
+        &job_id.unwrap_or_else(|| nanoid!()),
+        &options,
+    )
+    .await
+}
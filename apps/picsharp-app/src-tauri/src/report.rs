@@ -0,0 +1,371 @@
+use crate::file::get_file_extension;
+use crate::history::JobHistory;
+use crate::image_processor::common::{
+    calculate_compress_rate, CompressionResult, CompressionStatus, SkipReason,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Runtime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReportFormat {
+    #[serde(rename = "csv")]
+    Csv,
+    #[serde(rename = "json")]
+    Json,
+    // 单文件 HTML，样式内联，可直接发送或附在 PR 中
+    #[serde(rename = "html")]
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            "html" | "htm" => Ok(ReportFormat::Html),
+            _ => Err(format!("Unsupported report format: {}", s)),
+        }
+    }
+}
+
+impl ReportFormat {
+    /// 根据输出文件扩展名推断格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        ReportFormat::from_str(&get_file_extension(path)).ok()
+    }
+}
+
+const HTML_STYLE: &str = "\
+body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,sans-serif;margin:32px;color:#1f2328;background:#fff}\
+h1{font-size:22px;margin:0 0 4px}h2{font-size:16px;margin:28px 0 8px}\
+.meta{color:#656d76;font-size:13px;margin-bottom:20px}\
+.cards{display:flex;gap:12px;flex-wrap:wrap}\
+.card{border:1px solid #d0d7de;border-radius:8px;padding:10px 16px;min-width:120px}\
+.card .label{color:#656d76;font-size:12px}.card .value{font-size:18px;font-weight:600}\
+table{border-collapse:collapse;width:100%;font-size:13px}\
+th,td{border-bottom:1px solid #d0d7de;padding:6px 8px;text-align:left;vertical-align:top}\
+th{background:#f6f8fa}td.num{text-align:right;white-space:nowrap}\
+.tag{display:inline-block;border-radius:10px;padding:1px 8px;margin:1px 2px;font-size:12px;background:#ddf4ff;color:#0969da}\
+.tag.warn{background:#fff8c5;color:#9a6700}.tag.error{background:#ffebe9;color:#cf222e}.tag.ok{background:#dafbe1;color:#1a7f37}";
+
+/// 转义 HTML 特殊字符
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 按 RFC 4180 转义 CSV 字段
+pub fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 以 1024 为基数格式化字节数
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.2} {}", size, UNITS[unit])
+    }
+}
+
+/// 汇总卡片，`cards` 中为 (标题, 值)，值需已转义
+pub fn html_cards(cards: &[(&str, String)]) -> String {
+    let mut html = String::from("<div class=\"cards\">");
+    for (label, value) in cards {
+        let _ = write!(
+            html,
+            "<div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div></div>",
+            escape_html(label),
+            value
+        );
+    }
+    html.push_str("</div>");
+    html
+}
+
+/// 生成自包含的 HTML 页面，`body` 需已转义
+pub fn html_document(title: &str, meta: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{style}</style>\n</head>\n<body>\n<h1>{title}</h1>\n<div class=\"meta\">{meta}</div>\n{body}\n</body>\n</html>\n",
+        title = escape_html(title),
+        meta = escape_html(meta),
+        style = HTML_STYLE,
+        body = body,
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportItem {
+    pub input_path: String,
+    pub output_path: String,
+    // 按扩展名归类的格式
+    pub format: String,
+    pub status: String,
+    pub original_bytes_size: u64,
+    pub compressed_bytes_size: u64,
+    pub compress_rate: f64,
+    pub cost_time: u64,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReportTotals {
+    pub count: usize,
+    pub success_count: usize,
+    pub failed_count: usize,
+    pub skipped_count: usize,
+    pub original_bytes_size: u64,
+    pub compressed_bytes_size: u64,
+    pub saved_bytes_size: u64,
+    pub compress_rate: f64,
+    pub cost_time: u64,
+}
+
+impl ReportTotals {
+    fn add(&mut self, item: &ReportItem) {
+        self.count += 1;
+        match item.status.as_str() {
+            "success" => self.success_count += 1,
+            "failed" => self.failed_count += 1,
+            _ => self.skipped_count += 1,
+        }
+        // 失败的文件没有输出，不计入大小统计
+        if item.status != "failed" {
+            self.original_bytes_size += item.original_bytes_size;
+            self.compressed_bytes_size += item.compressed_bytes_size;
+        }
+        self.cost_time += item.cost_time;
+        self.saved_bytes_size = self
+            .original_bytes_size
+            .saturating_sub(self.compressed_bytes_size);
+        self.compress_rate = if self.original_bytes_size == 0 {
+            0.0
+        } else {
+            calculate_compress_rate(self.original_bytes_size, self.compressed_bytes_size)
+        };
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormatBreakdown {
+    pub format: String,
+    #[serde(flatten)]
+    pub totals: ReportTotals,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionReport {
+    pub job_id: String,
+    // 生成时间，Unix 秒
+    pub generated_at: u64,
+    pub totals: ReportTotals,
+    pub formats: Vec<FormatBreakdown>,
+    pub items: Vec<ReportItem>,
+}
+
+fn status_label(status: &CompressionStatus) -> &'static str {
+    match status {
+        CompressionStatus::Success => "success",
+        CompressionStatus::Failed => "failed",
+        CompressionStatus::Skipped(SkipReason::AlreadyOptimized) => "skipped_already_optimized",
+        CompressionStatus::Skipped(SkipReason::OutputExists) => "skipped_output_exists",
+    }
+}
+
+impl CompressionReport {
+    pub fn from_results(job_id: &str, results: &[CompressionResult]) -> Self {
+        let items: Vec<ReportItem> = results
+            .iter()
+            .map(|result| ReportItem {
+                input_path: result.input_path.clone(),
+                output_path: result.output_path.clone(),
+                format: get_file_extension(Path::new(&result.input_path)),
+                status: status_label(&result.status).to_string(),
+                original_bytes_size: result.original_bytes_size,
+                compressed_bytes_size: result.compressed_bytes_size,
+                compress_rate: result.compress_rate,
+                cost_time: result.cost_time,
+                error_message: result.error_message.clone(),
+            })
+            .collect();
+
+        let mut totals = ReportTotals::default();
+        let mut formats: BTreeMap<String, ReportTotals> = BTreeMap::new();
+        for item in &items {
+            totals.add(item);
+            // jpg 与 jpeg 归为同一类
+            let format = match item.format.as_str() {
+                "jpeg" => "jpg".to_string(),
+                format => format.to_string(),
+            };
+            formats.entry(format).or_default().add(item);
+        }
+
+        Self {
+            job_id: job_id.to_string(),
+            generated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            totals,
+            formats: formats
+                .into_iter()
+                .map(|(format, totals)| FormatBreakdown { format, totals })
+                .collect(),
+            items,
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "input_path,output_path,format,status,original_bytes_size,compressed_bytes_size,compress_rate,cost_time,error_message\n",
+        );
+        for item in &self.items {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{}",
+                escape_csv(&item.input_path),
+                escape_csv(&item.output_path),
+                escape_csv(&item.format),
+                item.status,
+                item.original_bytes_size,
+                item.compressed_bytes_size,
+                item.compress_rate,
+                item.cost_time,
+                escape_csv(item.error_message.as_deref().unwrap_or_default()),
+            );
+        }
+        csv
+    }
+
+    pub fn to_html(&self) -> String {
+        let totals = &self.totals;
+        let mut body = html_cards(&[
+            ("Files", totals.count.to_string()),
+            ("Succeeded", totals.success_count.to_string()),
+            ("Failed", totals.failed_count.to_string()),
+            ("Skipped", totals.skipped_count.to_string()),
+            (
+                "Original size",
+                escape_html(&format_bytes(totals.original_bytes_size)),
+            ),
+            (
+                "Compressed size",
+                escape_html(&format_bytes(totals.compressed_bytes_size)),
+            ),
+            (
+                "Saved",
+                format!(
+                    "{} ({}%)",
+                    escape_html(&format_bytes(totals.saved_bytes_size)),
+                    totals.compress_rate
+                ),
+            ),
+        ]);
+
+        body.push_str("<h2>By format</h2>");
+        body.push_str("<table><thead><tr><th>Format</th><th>Files</th><th>Original</th><th>Compressed</th><th>Saved</th><th>Rate</th></tr></thead><tbody>");
+        for breakdown in &self.formats {
+            let totals = &breakdown.totals;
+            let _ = write!(
+                body,
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}%</td></tr>",
+                escape_html(&breakdown.format.to_uppercase()),
+                totals.count,
+                escape_html(&format_bytes(totals.original_bytes_size)),
+                escape_html(&format_bytes(totals.compressed_bytes_size)),
+                escape_html(&format_bytes(totals.saved_bytes_size)),
+                totals.compress_rate,
+            );
+        }
+        body.push_str("</tbody></table>");
+
+        body.push_str("<h2>Files</h2>");
+        body.push_str("<table><thead><tr><th>File</th><th>Status</th><th>Original</th><th>Compressed</th><th>Rate</th><th>Time</th><th>Error</th></tr></thead><tbody>");
+        for item in &self.items {
+            let tag_class = match item.status.as_str() {
+                "success" => "ok",
+                "failed" => "error",
+                _ => "warn",
+            };
+            let _ = write!(
+                body,
+                "<tr><td title=\"{output}\">{input}</td><td><span class=\"tag {tag_class}\">{status}</span></td><td class=\"num\">{original}</td><td class=\"num\">{compressed}</td><td class=\"num\">{rate}%</td><td class=\"num\">{cost} ms</td><td>{error}</td></tr>",
+                output = escape_html(&item.output_path),
+                input = escape_html(&item.input_path),
+                tag_class = tag_class,
+                status = escape_html(&item.status),
+                original = escape_html(&format_bytes(item.original_bytes_size)),
+                compressed = escape_html(&format_bytes(item.compressed_bytes_size)),
+                rate = item.compress_rate,
+                cost = item.cost_time,
+                error = escape_html(item.error_message.as_deref().unwrap_or_default()),
+            );
+        }
+        body.push_str("</tbody></table>");
+
+        html_document(
+            "PicSharp Compression Report",
+            &format!("Job {}", self.job_id),
+            &body,
+        )
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Csv => self.to_csv(),
+            ReportFormat::Json => serde_json::to_string_pretty(self).unwrap_or_default(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    pub fn write(&self, format: ReportFormat, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.render(format))
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_export_report<R: Runtime>(
+    app: AppHandle<R>,
+    job_id: String,
+    format: ReportFormat,
+    path: String,
+) -> Result<(), String> {
+    let history = JobHistory::from_app(&app).map_err(|e| e.to_string())?;
+    let record = history.load(&job_id).map_err(|e| e.to_string())?;
+    CompressionReport::from_results(&record.job_id, &record.results)
+        .write(format, Path::new(&path))
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,432 @@
+use glob::{MatchOptions, Pattern};
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+const PICSHARPIGNORE_FILE_NAME: &str = ".picsharpignore";
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HiddenPolicy {
+    // 包含以 `.` 开头的文件和文件夹
+    #[serde(rename = "include")]
+    Include,
+    // 跳过以 `.` 开头的文件和文件夹
+    #[serde(rename = "exclude")]
+    Exclude,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymlinkPolicy {
+    // 跟随符号链接，链接成环时自动跳过
+    #[serde(rename = "follow")]
+    Follow,
+    // 跳过符号链接
+    #[serde(rename = "skip")]
+    Skip,
+}
+
+/// 遍历文件夹时的过滤规则，`parse_paths` 与 `count_valid_files` 共用，保证两者结果一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanOptions {
+    // 文件需要匹配其中之一，为空时不限制。相对拖入路径匹配，不含 `/` 时匹配文件名
+    pub include: Vec<String>,
+    // 匹配的文件或文件夹会被跳过
+    pub exclude: Vec<String>,
+    pub min_bytes_size: Option<u64>,
+    pub max_bytes_size: Option<u64>,
+    // 最大遍历深度，拖入的路径本身深度为 0
+    pub max_depth: Option<usize>,
+    pub hidden: HiddenPolicy,
+    pub symlinks: SymlinkPolicy,
+    pub respect_gitignore: bool,
+    pub respect_picsharpignore: bool,
+    // 读取文件头中的尺寸、颜色类型、动画等信息，会增加扫描耗时
+    pub with_image_info: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            min_bytes_size: None,
+            max_bytes_size: None,
+            max_depth: None,
+            hidden: HiddenPolicy::Include,
+            symlinks: SymlinkPolicy::Follow,
+            respect_gitignore: false,
+            respect_picsharpignore: true,
+            with_image_info: false,
+        }
+    }
+}
+
+// 使用 `/` 分隔的相对路径，保证各平台匹配规则一致
+fn to_slash(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn compile_patterns(patterns: &[String]) -> Vec<Pattern> {
+    patterns
+        .iter()
+        .filter_map(|pattern| match Pattern::new(pattern) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                error!("[scan] -> Invalid glob pattern <{}>: {}", pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
+// 不含 `/` 的规则匹配文件名，否则匹配相对路径
+fn matches_any(patterns: &[Pattern], relative: &str, name: &str) -> bool {
+    patterns.iter().any(|pattern| {
+        if pattern.as_str().contains('/') {
+            pattern.matches_with(relative, MATCH_OPTIONS)
+        } else {
+            pattern.matches_with(name, MATCH_OPTIONS)
+        }
+    })
+}
+
+#[derive(Debug)]
+struct IgnoreRule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    // 含有 `/` 的规则相对忽略文件所在目录匹配，否则匹配任意层级的名称
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.trim_start_matches('/');
+        if line.is_empty() {
+            return None;
+        }
+        let pattern = Pattern::new(line).ok()?;
+        Some(Self {
+            pattern,
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, relative: &str, name: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            self.pattern.matches_with(relative, MATCH_OPTIONS)
+        } else {
+            self.pattern.matches_with(name, MATCH_OPTIONS)
+        }
+    }
+}
+
+/// 按目录懒加载 `.gitignore` / `.picsharpignore` 规则
+struct IgnoreFiles {
+    root: PathBuf,
+    file_names: Vec<&'static str>,
+    rules: HashMap<PathBuf, Vec<IgnoreRule>>,
+}
+
+impl IgnoreFiles {
+    fn new(root: PathBuf, options: &ScanOptions) -> Self {
+        let mut file_names = Vec::new();
+        if options.respect_gitignore {
+            file_names.push(GITIGNORE_FILE_NAME);
+        }
+        if options.respect_picsharpignore {
+            file_names.push(PICSHARPIGNORE_FILE_NAME);
+        }
+        Self {
+            root,
+            file_names,
+            rules: HashMap::new(),
+        }
+    }
+
+    fn rules_for(&mut self, dir: &Path) -> &[IgnoreRule] {
+        let file_names = &self.file_names;
+        self.rules.entry(dir.to_path_buf()).or_insert_with(|| {
+            file_names
+                .iter()
+                .filter_map(|file_name| fs::read_to_string(dir.join(file_name)).ok())
+                .flat_map(|content| {
+                    content
+                        .lines()
+                        .filter_map(IgnoreRule::parse)
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        })
+    }
+
+    /// 从遍历根目录开始逐级应用规则，越深的规则优先级越高，同一文件中后写的规则优先
+    fn is_ignored(&mut self, relative: &str, is_dir: bool) -> bool {
+        if self.file_names.is_empty() || relative.is_empty() {
+            return false;
+        }
+        let parts: Vec<&str> = relative.split('/').collect();
+        let name = parts[parts.len() - 1];
+
+        let mut ignored = false;
+        let mut dir = self.root.clone();
+        for depth in 0..parts.len() {
+            if depth > 0 {
+                dir.push(parts[depth - 1]);
+            }
+            let relative_to_dir = parts[depth..].join("/");
+            for rule in self.rules_for(&dir) {
+                if rule.matches(&relative_to_dir, name, is_dir) {
+                    ignored = !rule.negated;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+fn is_hidden(entry: &DirEntry) -> bool {
+    entry.depth() > 0 && entry.file_name().to_string_lossy().starts_with('.')
+}
+
+// 扩展名统一为小写且不带 `.`
+pub(crate) fn normalize_exts(valid_exts: &[String]) -> HashSet<String> {
+    valid_exts
+        .iter()
+        .map(|ext| ext.trim_start_matches('.').to_lowercase())
+        .collect()
+}
+
+fn entry_extension(entry: &DirEntry) -> String {
+    entry
+        .path()
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// 按 `ScanOptions` 遍历路径，返回扩展名在 `valid_exts` 中且通过过滤的文件，扩展名不区分大小写
+pub fn walk_files(
+    path: &Path,
+    valid_exts: &[String],
+    options: &ScanOptions,
+) -> impl Iterator<Item = DirEntry> {
+    let valid_exts = normalize_exts(valid_exts);
+    // 拖入的是文件时，相对路径与忽略规则以其所在目录为准
+    let root = if path.is_dir() {
+        path.to_path_buf()
+    } else {
+        path.parent().map(Path::to_path_buf).unwrap_or_default()
+    };
+    let mut ignore_files = IgnoreFiles::new(root.clone(), options);
+    let relative_of = move |entry: &DirEntry| {
+        entry
+            .path()
+            .strip_prefix(&root)
+            .map(to_slash)
+            .unwrap_or_default()
+    };
+
+    let include = compile_patterns(&options.include);
+    let exclude = compile_patterns(&options.exclude);
+    let (hidden, symlinks) = (options.hidden, options.symlinks);
+    let (min_bytes_size, max_bytes_size) = (options.min_bytes_size, options.max_bytes_size);
+
+    let mut walker = WalkDir::new(path).follow_links(symlinks == SymlinkPolicy::Follow);
+    if let Some(max_depth) = options.max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+
+    let entry_relative_of = relative_of.clone();
+    walker
+        .into_iter()
+        .filter_entry(move |entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            if symlinks == SymlinkPolicy::Skip && entry.path_is_symlink() {
+                return false;
+            }
+            if hidden == HiddenPolicy::Exclude && is_hidden(entry) {
+                return false;
+            }
+            let relative = entry_relative_of(entry);
+            let name = entry.file_name().to_string_lossy();
+            !matches_any(&exclude, &relative, &name)
+                && !ignore_files.is_ignored(&relative, entry.file_type().is_dir())
+        })
+        .filter_map(|e| e.ok())
+        .filter(move |entry| {
+            if !entry.file_type().is_file() || !valid_exts.contains(&entry_extension(entry)) {
+                return false;
+            }
+            let name = entry.file_name().to_string_lossy();
+            if !include.is_empty() && !matches_any(&include, &relative_of(entry), &name) {
+                return false;
+            }
+            if min_bytes_size.is_none() && max_bytes_size.is_none() {
+                return true;
+            }
+            let Ok(md) = entry.metadata() else {
+                return false;
+            };
+            min_bytes_size.is_none_or(|min| md.len() >= min)
+                && max_bytes_size.is_none_or(|max| md.len() <= max)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn ignore_files(root: &Path) -> IgnoreFiles {
+        IgnoreFiles::new(root.to_path_buf(), &ScanOptions::default())
+    }
+
+    #[test]
+    fn parses_ignore_lines() {
+        assert!(IgnoreRule::parse("# comment").is_none());
+        assert!(IgnoreRule::parse("   ").is_none());
+        assert!(IgnoreRule::parse("/").is_none());
+
+        let rule = IgnoreRule::parse("!build/").unwrap();
+        assert!(rule.negated && rule.dir_only && !rule.anchored);
+        let rule = IgnoreRule::parse("\\!important.png").unwrap();
+        assert!(!rule.negated);
+        assert_eq!(rule.pattern.as_str(), "!important.png");
+        let rule = IgnoreRule::parse("/top.png").unwrap();
+        assert!(rule.anchored);
+        assert_eq!(rule.pattern.as_str(), "top.png");
+    }
+
+    #[test]
+    fn negation_re_includes_later_matches() {
+        let root = temp_dir();
+        write(&root, PICSHARPIGNORE_FILE_NAME, "*.png\n!keep.png\n");
+        let mut ignore = ignore_files(&root);
+        assert!(ignore.is_ignored("a.png", false));
+        assert!(ignore.is_ignored("sub/a.png", false));
+        assert!(!ignore.is_ignored("keep.png", false));
+        assert!(!ignore.is_ignored("sub/keep.png", false));
+        assert!(!ignore.is_ignored("a.jpg", false));
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn dir_only_rule_skips_files_with_same_name() {
+        let root = temp_dir();
+        write(&root, PICSHARPIGNORE_FILE_NAME, "build/\n");
+        let mut ignore = ignore_files(&root);
+        assert!(ignore.is_ignored("build", true));
+        assert!(ignore.is_ignored("sub/build", true));
+        assert!(!ignore.is_ignored("build", false));
+        assert!(!ignore.is_ignored("sub/build", false));
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn anchored_rules_match_relative_to_ignore_file() {
+        let root = temp_dir();
+        write(
+            &root,
+            PICSHARPIGNORE_FILE_NAME,
+            "/top.png\nassets/*.png\nlogo.png\n",
+        );
+        let mut ignore = ignore_files(&root);
+        // 以 `/` 开头只匹配忽略文件所在目录
+        assert!(ignore.is_ignored("top.png", false));
+        assert!(!ignore.is_ignored("sub/top.png", false));
+        // 中间含 `/` 同样相对忽略文件所在目录，`*` 不跨目录
+        assert!(ignore.is_ignored("assets/a.png", false));
+        assert!(!ignore.is_ignored("assets/icons/a.png", false));
+        assert!(!ignore.is_ignored("sub/assets/a.png", false));
+        // 不含 `/` 匹配任意层级的名称
+        assert!(ignore.is_ignored("logo.png", false));
+        assert!(ignore.is_ignored("sub/deep/logo.png", false));
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn nested_ignore_files_override_parent_rules() {
+        let root = temp_dir();
+        write(&root, PICSHARPIGNORE_FILE_NAME, "*.png\n");
+        write(&root, "sub/.picsharpignore", "!*.png\n/local.jpg\n");
+        let mut ignore = ignore_files(&root);
+        assert!(ignore.is_ignored("a.png", false));
+        assert!(!ignore.is_ignored("sub/a.png", false));
+        assert!(!ignore.is_ignored("sub/deep/a.png", false));
+        // 子目录中的锚定规则相对子目录匹配
+        assert!(ignore.is_ignored("sub/local.jpg", false));
+        assert!(!ignore.is_ignored("local.jpg", false));
+        assert!(!ignore.is_ignored("sub/deep/local.jpg", false));
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn walk_skips_ignored_dirs_and_respects_gitignore_option() {
+        let root = temp_dir();
+        write(&root, PICSHARPIGNORE_FILE_NAME, "build/\n");
+        write(&root, GITIGNORE_FILE_NAME, "*.jpg\n");
+        write(&root, "build/a.png", "");
+        write(&root, "src/a.png", "");
+        write(&root, "src/b.jpg", "");
+        let exts = vec!["png".to_string(), "jpg".to_string()];
+        let walk = |options: &ScanOptions| -> Vec<String> {
+            let mut files: Vec<String> = walk_files(&root, &exts, options)
+                .map(|entry| to_slash(entry.path().strip_prefix(&root).unwrap()))
+                .collect();
+            files.sort();
+            files
+        };
+
+        assert_eq!(
+            walk(&ScanOptions::default()),
+            vec!["src/a.png", "src/b.jpg"]
+        );
+        let options = ScanOptions {
+            respect_gitignore: true,
+            ..ScanOptions::default()
+        };
+        assert_eq!(walk(&options), vec!["src/a.png"]);
+        let _ = fs::remove_dir_all(root);
+    }
+}
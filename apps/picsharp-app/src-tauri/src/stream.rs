@@ -1,10 +1,11 @@
+use crate::http::HttpConfig;
 use futures_util::StreamExt;
 use reqwest::header::{HeaderMap, HeaderName};
-use reqwest::Client;
 use std::collections::HashMap;
 use std::error::Error;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
+use tauri::Manager;
 
 static REQUEST_COUNTER: AtomicU32 = AtomicU32::new(0);
 
@@ -52,7 +53,10 @@ pub async fn stream_fetch(
     let method = method
         .parse::<reqwest::Method>()
         .map_err(|err| format!("failed to parse method: {}", err))?;
-    let client = Client::builder()
+    // 与其他外部请求共用代理与根证书设置
+    let client = HttpConfig::from_store(window.app_handle())
+        .client_builder()
+        .map_err(|err| format!("failed to generate client: {}", err))?
         .default_headers(_headers)
         .redirect(reqwest::redirect::Policy::limited(3))
         .connect_timeout(Duration::new(3, 0))
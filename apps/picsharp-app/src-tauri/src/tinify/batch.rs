@@ -0,0 +1,164 @@
+use super::TinifyCompressor;
+use crate::file::get_file_bytes_size;
+use crate::image_processor::common::{CompressionOptions, CompressionResult, CompressionStatus};
+use crate::image_processor::compressors::{
+    create_compression_options_from_store, source_roots, CompressionContext,
+};
+use crate::remote::remote_to_output;
+use futures_util::future::join_all;
+use log::{error, info};
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Runtime};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_CONCURRENCY: usize = 6;
+
+// 正在进行的批量任务，以 job id 为键，值为取消令牌
+static RUNNING_BATCHES: Mutex<Vec<(String, CancellationToken)>> = Mutex::new(Vec::new());
+
+fn get_concurrency<R: Runtime>(app: &AppHandle<R>) -> usize {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get("concurrency"))
+        .and_then(|v| v.as_u64())
+        .map(|n| n.clamp(1, 32) as usize)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+}
+
+fn failed_result(
+    input_path: &str,
+    job_id: &str,
+    error: String,
+    cost_time: u64,
+) -> CompressionResult {
+    CompressionResult {
+        input_path: input_path.to_string(),
+        output_path_converted: input_path.to_string(),
+        compressed_bytes_size: 0,
+        compressed_disk_size: 0,
+        output_path: "".to_string(),
+        status: CompressionStatus::Failed,
+        original_bytes_size: get_file_bytes_size(input_path, None).unwrap_or(0),
+        error_message: Some(error),
+        compress_rate: 0.0,
+        cost_time,
+        original_temp_path: "".to_string(),
+        job_id: job_id.to_string(),
+        backup_id: None,
+    }
+}
+
+/// 并发压缩一批文件，同时进行的请求数受 semaphore 限制。
+/// 取消后不再开始新的文件，已在上传或下载的文件会完成以免留下不完整的输出。
+async fn tinify_batch<R: Runtime>(
+    app: &AppHandle<R>,
+    webview_window: &tauri::WebviewWindow,
+    compressor: &TinifyCompressor<R>,
+    paths: Vec<String>,
+    context: &CompressionContext,
+    options: &CompressionOptions,
+    cancelled: &CancellationToken,
+) -> Vec<CompressionResult> {
+    let semaphore = Semaphore::new(get_concurrency(app));
+
+    let tasks = paths.iter().map(|path| {
+        let semaphore = &semaphore;
+        async move {
+            let _permit = tokio::select! {
+                permit = semaphore.acquire() => permit.ok()?,
+                _ = cancelled.cancelled() => return None,
+            };
+            if cancelled.is_cancelled() {
+                return None;
+            }
+
+            let start = Instant::now();
+            let result = match remote_to_output(compressor, app, path, &context.job_id, options)
+                .await
+            {
+                Ok(result) => result.into_compression_result(start.elapsed().as_millis() as u64),
+                Err(e) => {
+                    failed_result(path, &context.job_id, e, start.elapsed().as_millis() as u64)
+                }
+            };
+            let _ = webview_window.emit("compression-progress", serde_json::json!(result));
+            Some(result)
+        }
+    });
+
+    join_all(tasks).await.into_iter().flatten().collect()
+}
+
+/// 使用 TinyPNG 批量压缩，进度与完成事件与本地引擎一致。
+/// 任务在后台执行，立即返回 job id，可用于 `ipc_cancel_tinify_batch` 取消
+#[tauri::command]
+pub async fn ipc_tinify_batch<R: Runtime>(
+    app: AppHandle<R>,
+    webview_window: tauri::WebviewWindow,
+    paths: Vec<String>,
+    roots: Option<Vec<String>>,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    let mut options = create_compression_options_from_store(&app)?;
+    options.source_roots = source_roots(roots);
+    let compressor = TinifyCompressor::from_app(&app).map_err(|e| e.to_string())?;
+
+    let context = CompressionContext::from_app(&app, job_id);
+    let job_id = context.job_id.clone();
+    let cancelled = CancellationToken::new();
+    {
+        let mut batches = RUNNING_BATCHES.lock().unwrap_or_else(|e| e.into_inner());
+        if batches.iter().any(|(id, _)| id == &job_id) {
+            return Err(format!("Batch already running: {}", job_id));
+        }
+        batches.push((job_id.clone(), cancelled.clone()));
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let results = tinify_batch(
+            &app,
+            &webview_window,
+            &compressor,
+            paths,
+            &context,
+            &options,
+            &cancelled,
+        )
+        .await;
+
+        RUNNING_BATCHES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(job_id, _)| job_id != &context.job_id);
+
+        if cancelled.is_cancelled() {
+            info!(
+                "TinyPNG batch {} cancelled after {} files",
+                context.job_id,
+                results.len()
+            );
+        }
+        context.record_results(&results);
+        context.finish();
+
+        if let Err(e) = webview_window.emit("compression-completed", serde_json::json!(results)) {
+            error!("Failed to emit compression-completed: {}", e);
+        }
+    });
+
+    Ok(job_id)
+}
+
+#[tauri::command]
+pub async fn ipc_cancel_tinify_batch(job_id: String) -> Result<(), String> {
+    let batches = RUNNING_BATCHES.lock().unwrap_or_else(|e| e.into_inner());
+    let (_, cancelled) = batches
+        .iter()
+        .find(|(id, _)| id == &job_id)
+        .ok_or_else(|| format!("Batch not found: {}", job_id))?;
+    cancelled.cancel();
+    Ok(())
+}
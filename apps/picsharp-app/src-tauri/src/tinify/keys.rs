@@ -0,0 +1,697 @@
+use super::secret::{is_key_id, key_id, mask_api_key, SecretBackend, TinifyKeyVault};
+use super::{api_error_message, shrink_url, Result, TinifyApiKey, TinifyError};
+use crate::http::{send_with_retry, HttpConfig};
+use log::{error, info, warn};
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+
+const KEY_USAGE_FILE_NAME: &str = "tinify_keys.json";
+// 免费 key 每月可压缩的图片数
+pub const DEFAULT_MONTHLY_LIMIT: u64 = 500;
+// 每个 key 每秒最多发起的请求数
+pub const DEFAULT_REQUESTS_PER_SECOND: f64 = 5.0;
+// 用量变化后延迟保存，合并同一批请求产生的多次写入
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyState {
+    #[serde(rename = "active")]
+    Active,
+    // 本月额度已用完（429），到 reset_at 后恢复
+    #[serde(rename = "exhausted")]
+    Exhausted,
+    // key 无效或已被停用（401）
+    #[serde(rename = "invalid")]
+    Invalid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyUsage {
+    state: KeyState,
+    // 最近一次响应中的 Compression-Count
+    compression_count: Option<u64>,
+    // 计数所属月份的结束时间（unix 秒），过后计数清零
+    reset_at: u64,
+    last_used_at: Option<u64>,
+    last_error: Option<String>,
+}
+
+impl KeyUsage {
+    fn new(now: u64) -> Self {
+        Self {
+            state: KeyState::Active,
+            compression_count: None,
+            reset_at: next_month_start(now),
+            last_used_at: None,
+            last_error: None,
+        }
+    }
+
+    // 跨月后恢复额度，无效的 key 保持无效
+    fn refresh(&mut self, now: u64) {
+        if now >= self.reset_at {
+            self.compression_count = None;
+            self.reset_at = next_month_start(now);
+            if self.state == KeyState::Exhausted {
+                self.state = KeyState::Active;
+                self.last_error = None;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinifyKeyStatus {
+    pub id: String,
+    // 打码后的 key
+    pub api_key: String,
+    pub state: KeyState,
+    pub compression_count: Option<u64>,
+    pub monthly_limit: u64,
+    // 按 monthly_limit 估算的剩余次数，额度用完时为 0
+    pub remaining: Option<u64>,
+    pub reset_at: u64,
+    pub last_used_at: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// 公历日期转为 1970-01-01 起的天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// TinyPNG 按自然月（UTC）重置压缩次数，返回下个月第一天 0 点的 unix 秒
+fn next_month_start(secs: u64) -> u64 {
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let (year, month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    (days_from_civil(year, month, 1) * 86400) as u64
+}
+
+// 设置中的 key 列表，旧版本使用 compression_tinypng_api_keys
+fn get_key_entries<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<Value>> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| TinifyError::StoreError(e.to_string()))?;
+    let api_keys_value = store
+        .get("tinypng_api_keys")
+        .or_else(|| store.get("compression_tinypng_api_keys"))
+        .unwrap_or(Value::Array(vec![]));
+    Ok(serde_json::from_value(api_keys_value)?)
+}
+
+fn save_key_entries<R: Runtime>(app: &AppHandle<R>, entries: Vec<Value>) -> Result<()> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| TinifyError::StoreError(e.to_string()))?;
+    store.set("tinypng_api_keys", Value::Array(entries));
+    store.delete("compression_tinypng_api_keys");
+    store
+        .save()
+        .map_err(|e| TinifyError::StoreError(e.to_string()))
+}
+
+fn entry_id(entry: &Value) -> Option<String> {
+    entry.get("id").and_then(|v| v.as_str()).map(String::from)
+}
+
+// 设置中 api_key 的 sha256 与 id 一致时为明文，否则为打码后的值
+fn plaintext_key(entry: &TinifyApiKey) -> Option<String> {
+    let api_key = entry.api_key.trim();
+    let is_plaintext = entry.id.as_deref().is_none_or(|id| id == key_id(api_key));
+    (is_plaintext && !api_key.is_empty()).then(|| api_key.to_string())
+}
+
+/// 将设置中的明文 key 存入安全存储，设置中只保留 id 与打码后的 key，
+/// 并清理安全存储中已从设置删除的 key。没有可用的安全存储时只补全 id。
+/// 只在启动时调用。
+pub fn migrate_plaintext_keys<R: Runtime>(app: &AppHandle<R>) -> Result<()> {
+    let vault = app.state::<TinifyKeyVault>();
+    let _edits = vault.lock_edits();
+    let mut entries = get_key_entries(app)?;
+    let mut migrated = 0;
+    for entry in entries.iter_mut() {
+        let Ok(key) = serde_json::from_value::<TinifyApiKey>(entry.clone()) else {
+            continue;
+        };
+        let (Some(object), Some(api_key)) = (entry.as_object_mut(), plaintext_key(&key)) else {
+            continue;
+        };
+        let id = match vault.backend() {
+            Some(_) => {
+                object.insert("api_key".to_string(), Value::String(mask_api_key(&api_key)));
+                vault.insert(&api_key)?
+            }
+            None if key.id.is_some() => continue,
+            None => key_id(&api_key),
+        };
+        object.insert("id".to_string(), Value::String(id));
+        migrated += 1;
+    }
+
+    if migrated > 0 {
+        save_key_entries(app, entries.clone())?;
+        info!(
+            "[TinifyKeyVault] -> Migrated {} API keys in settings",
+            migrated
+        );
+    }
+    let ids: Vec<String> = entries.iter().filter_map(entry_id).collect();
+    vault.retain(&ids)
+}
+
+/// 读取设置中的 key 记录，api_key 为明文，已迁移的 key 从安全存储中取出
+pub fn get_key_records<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<TinifyKeyEntry>> {
+    let vault = app.state::<TinifyKeyVault>();
+    let entries: Vec<TinifyApiKey> = serde_json::from_value(Value::Array(get_key_entries(app)?))?;
+    let mut records: Vec<TinifyKeyEntry> = Vec::new();
+    for entry in entries {
+        let stored = match &entry.id {
+            Some(id) if vault.backend().is_some() => vault.get(id)?,
+            _ => None,
+        };
+        // 打码后的 key 不能用于请求，安全存储中缺失时跳过
+        let Some(api_key) = stored.or_else(|| plaintext_key(&entry)) else {
+            warn!(
+                "[TinifyKeyVault] -> API key {} is missing from secure storage, add it again",
+                entry.name
+            );
+            continue;
+        };
+        if records.iter().any(|record| record.api_key == api_key) {
+            continue;
+        }
+        records.push(TinifyKeyEntry {
+            id: entry.id.unwrap_or_else(|| key_id(&api_key)),
+            name: entry.name,
+            api_key,
+        });
+    }
+    Ok(records)
+}
+
+/// 读取可用的 API key 列表
+pub fn get_api_keys<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<String>> {
+    Ok(get_key_records(app)?
+        .into_iter()
+        .map(|record| record.api_key)
+        .collect())
+}
+
+fn get_monthly_limit<R: Runtime>(app: &AppHandle<R>) -> u64 {
+    app.store("settings.json")
+        .ok()
+        .and_then(|store| store.get("tinypng_monthly_limit"))
+        .and_then(|v| v.as_u64())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(DEFAULT_MONTHLY_LIMIT)
+}
+
+/// 同一 key 两次请求之间的最小间隔，`tinypng_requests_per_second` 为 0 时不限制
+pub fn get_request_interval<R: Runtime>(app: &AppHandle<R>) -> Option<Duration> {
+    let per_second = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("tinypng_requests_per_second"))
+        .and_then(|v| v.as_f64())
+        .unwrap_or(DEFAULT_REQUESTS_PER_SECOND);
+    (per_second > 0.0).then(|| Duration::from_secs_f64(1.0 / per_second))
+}
+
+/// 按 key 限制请求速率，同一 key 的请求至少间隔 interval，超出的请求排队等待
+#[derive(Debug, Default)]
+pub struct KeyRateLimiter {
+    // 每个 key 下一个可用的请求时间
+    next_slots: Mutex<HashMap<String, Instant>>,
+}
+
+impl KeyRateLimiter {
+    pub async fn wait(&self, api_key: &str, interval: Duration) {
+        let delay = {
+            let mut slots = self.next_slots.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let slot = slots
+                .get(api_key)
+                .copied()
+                .filter(|slot| *slot > now)
+                .unwrap_or(now);
+            slots.insert(api_key.to_string(), slot + interval);
+            slot - now
+        };
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// 跟踪每个 key 的本月用量与状态，选择 key 时跳过额度用完或无效的 key。
+/// 用量以 key id 为键保存在 `app_data_dir/tinify_keys.json`，重启后仍然有效。
+#[derive(Debug, Default)]
+pub struct TinifyKeyManager {
+    path: Option<PathBuf>,
+    usage: Arc<Mutex<HashMap<String, KeyUsage>>>,
+    // 已安排但尚未开始的保存
+    save_pending: Arc<AtomicBool>,
+    // 保证快照按顺序写入
+    save_lock: Arc<Mutex<()>>,
+}
+
+fn write_usage(path: &Path, usage: &HashMap<String, KeyUsage>) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(path, serde_json::to_vec_pretty(usage).unwrap_or_default()));
+    if let Err(e) = result {
+        error!("[TinifyKeyManager] -> Failed to save key usage: {}", e);
+    }
+}
+
+impl TinifyKeyManager {
+    pub fn load(path: PathBuf) -> Self {
+        let usage: HashMap<String, KeyUsage> = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        // 旧版本以明文 key 为键，改为 key id 后重新保存
+        let legacy = usage.keys().any(|key| !is_key_id(key));
+        let usage = usage
+            .into_iter()
+            .map(|(key, entry)| match is_key_id(&key) {
+                true => (key, entry),
+                false => (key_id(&key), entry),
+            })
+            .collect();
+        if legacy {
+            write_usage(&path, &usage);
+        }
+        Self {
+            path: Some(path),
+            usage: Arc::new(Mutex::new(usage)),
+            ..Self::default()
+        }
+    }
+
+    pub fn from_app<R: Runtime>(app: &AppHandle<R>) -> Self {
+        match app.path().app_data_dir() {
+            Ok(dir) => Self::load(dir.join(KEY_USAGE_FILE_NAME)),
+            Err(e) => {
+                error!("[TinifyKeyManager] -> Failed to get app data dir: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    // 在阻塞线程中延迟保存，写文件时不持有用量锁
+    fn schedule_save(&self) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if self.save_pending.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let usage = self.usage.clone();
+        let save_pending = self.save_pending.clone();
+        let save_lock = self.save_lock.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            std::thread::sleep(SAVE_DEBOUNCE);
+            let _guard = save_lock.lock().unwrap_or_else(|e| e.into_inner());
+            save_pending.store(false, Ordering::SeqCst);
+            let snapshot = usage.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            write_usage(&path, &snapshot);
+        });
+    }
+
+    fn update(&self, api_key: &str, f: impl FnOnce(&mut KeyUsage, u64)) {
+        let now = now_secs();
+        {
+            let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = usage
+                .entry(key_id(api_key))
+                .or_insert_with(|| KeyUsage::new(now));
+            entry.refresh(now);
+            f(entry, now);
+        }
+        self.schedule_save();
+    }
+
+    /// 从 `api_keys` 中选择一个可用的 key，跳过 `excluded` 中已尝试过的 key。
+    /// 优先选择本月用量最少的 key，用量相同时随机选择以分散请求。
+    pub fn acquire(&self, api_keys: &[String], excluded: &[String]) -> Result<String> {
+        let now = now_secs();
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let mut candidates: Vec<(&String, u64)> = api_keys
+            .iter()
+            .filter(|key| !excluded.contains(key))
+            .filter_map(|key| {
+                let entry = usage.get_mut(&key_id(key));
+                match entry {
+                    Some(entry) => {
+                        entry.refresh(now);
+                        (entry.state == KeyState::Active)
+                            .then_some((key, entry.compression_count.unwrap_or(0)))
+                    }
+                    None => Some((key, 0)),
+                }
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Err(TinifyError::NoApiKey);
+        }
+        candidates.shuffle(&mut rand::thread_rng());
+        candidates.sort_by_key(|(_, count)| *count);
+        Ok(candidates[0].0.clone())
+    }
+
+    /// 请求成功后记录响应中的 Compression-Count
+    pub fn record_success(&self, api_key: &str, compression_count: Option<u64>) {
+        self.update(api_key, |entry, now| {
+            entry.state = KeyState::Active;
+            if compression_count.is_some() {
+                entry.compression_count = compression_count;
+            }
+            entry.last_used_at = Some(now);
+            entry.last_error = None;
+        });
+    }
+
+    /// 本月额度已用完，到下个月前不再使用
+    pub fn mark_exhausted(&self, api_key: &str, message: &str) {
+        warn!("[TinifyKeyManager] -> Key exhausted: {}", message);
+        self.update(api_key, |entry, now| {
+            entry.state = KeyState::Exhausted;
+            entry.last_used_at = Some(now);
+            entry.last_error = Some(message.to_string());
+        });
+    }
+
+    /// key 无效，除非重新校验通过否则不再使用
+    pub fn mark_invalid(&self, api_key: &str, message: &str) {
+        warn!("[TinifyKeyManager] -> Key invalid: {}", message);
+        self.update(api_key, |entry, now| {
+            entry.state = KeyState::Invalid;
+            entry.last_used_at = Some(now);
+            entry.last_error = Some(message.to_string());
+        });
+    }
+
+    pub fn status(&self, api_keys: &[String], monthly_limit: u64) -> Vec<TinifyKeyStatus> {
+        let now = now_secs();
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+        api_keys
+            .iter()
+            .map(|key| {
+                let entry = usage
+                    .entry(key_id(key))
+                    .or_insert_with(|| KeyUsage::new(now));
+                entry.refresh(now);
+                let remaining = match entry.state {
+                    KeyState::Active => entry
+                        .compression_count
+                        .map(|count| monthly_limit.saturating_sub(count)),
+                    _ => Some(0),
+                };
+                TinifyKeyStatus {
+                    id: key_id(key),
+                    api_key: mask_api_key(key),
+                    state: entry.state,
+                    compression_count: entry.compression_count,
+                    monthly_limit,
+                    remaining,
+                    reset_at: entry.reset_at,
+                    last_used_at: entry.last_used_at,
+                    last_error: entry.last_error.clone(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinifyKeyValidation {
+    pub valid: bool,
+    // 本月已压缩次数
+    pub compression_count: Option<u64>,
+    // 账户错误，如 key 无效或额度已用完
+    pub error: Option<String>,
+}
+
+/// 按 TinyPNG 文档，不带图片数据请求 shrink 接口校验 key，不消耗压缩次数。
+/// key 有效时返回 400（InputMissing），无效时返回 401。
+pub async fn validate_api_key(
+    api_key: &str,
+    url: &str,
+    config: &HttpConfig,
+) -> Result<TinifyKeyValidation> {
+    let client = config
+        .client()
+        .map_err(|e| TinifyError::ApiKeyValidation(e.to_string()))?;
+    // 不带图片的请求不会被处理，可以安全重试
+    let response = send_with_retry(&config.retry, true, || async {
+        Ok::<_, reqwest::Error>(client.post(url).basic_auth("api", Some(api_key.trim())))
+    })
+    .await
+    .map_err(|e| TinifyError::ApiKeyValidation(e.to_string()))?;
+
+    let status = response.status().as_u16();
+    let compression_count = response
+        .headers()
+        .get("Compression-Count")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let message = api_error_message(&response.text().await.unwrap_or_default());
+
+    match status {
+        200..=299 | 400 => Ok(TinifyKeyValidation {
+            valid: true,
+            compression_count,
+            error: None,
+        }),
+        401 => Ok(TinifyKeyValidation {
+            valid: false,
+            compression_count,
+            error: Some(message),
+        }),
+        // key 有效，但本月额度已用完
+        429 => Ok(TinifyKeyValidation {
+            valid: true,
+            compression_count,
+            error: Some(message),
+        }),
+        _ => Err(TinifyError::ApiKeyValidation(format!(
+            "status {}: {}",
+            status, message
+        ))),
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_tinify_validate_key<R: Runtime>(
+    app: AppHandle<R>,
+    manager: tauri::State<'_, TinifyKeyManager>,
+    key: String,
+) -> std::result::Result<TinifyKeyValidation, TinifyError> {
+    // key 也可以是已保存 key 的 id
+    let key = key.trim().to_string();
+    let api_key = match is_key_id(&key) {
+        true => get_key_records(&app)?
+            .into_iter()
+            .find(|record| record.id == key)
+            .map(|record| record.api_key)
+            .unwrap_or(key),
+        false => key,
+    };
+    if api_key.is_empty() {
+        return Err(TinifyError::ApiKeyValidation(
+            "API key is empty".to_string(),
+        ));
+    }
+    let validation =
+        validate_api_key(&api_key, &shrink_url(&app), &HttpConfig::from_store(&app)).await?;
+    // 同步到用量记录，之前被标记为无效的 key 校验通过后可以重新使用
+    match (&validation.valid, &validation.error) {
+        (true, None) => manager.record_success(&api_key, validation.compression_count),
+        (true, Some(error)) => manager.mark_exhausted(&api_key, error),
+        (false, error) => manager.mark_invalid(&api_key, error.as_deref().unwrap_or_default()),
+    }
+    Ok(validation)
+}
+
+#[tauri::command]
+pub async fn ipc_tinify_key_status<R: Runtime>(
+    app: AppHandle<R>,
+    manager: tauri::State<'_, TinifyKeyManager>,
+) -> std::result::Result<Vec<TinifyKeyStatus>, String> {
+    let api_keys = get_api_keys(&app).map_err(|e| e.to_string())?;
+    Ok(manager.status(&api_keys, get_monthly_limit(&app)))
+}
+
+/// 设置中的 key 记录，添加 key 时返回打码后的 api_key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinifyKeyEntry {
+    pub id: String,
+    pub name: String,
+    pub api_key: String,
+}
+
+/// 在设置中添加 key 记录并保存到安全存储
+#[tauri::command]
+pub async fn ipc_tinify_add_key<R: Runtime>(
+    app: AppHandle<R>,
+    vault: tauri::State<'_, TinifyKeyVault>,
+    name: Option<String>,
+    key: String,
+) -> std::result::Result<TinifyKeyEntry, TinifyError> {
+    let api_key = key.trim().to_string();
+    if api_key.is_empty() {
+        return Err(TinifyError::SecretStore("API key is empty".to_string()));
+    }
+    let id = key_id(&api_key);
+    let name = name.unwrap_or_default();
+
+    // 持锁期间其他修改不会按旧列表清理安全存储；没有安全存储时明文保存在设置中
+    let _edits = vault.lock_edits();
+    let mut entries = get_key_entries(&app)?;
+    if !entries
+        .iter()
+        .any(|entry| entry_id(entry).as_deref() == Some(id.as_str()))
+    {
+        let stored_key = match vault.backend() {
+            Some(_) => {
+                vault.insert(&api_key)?;
+                mask_api_key(&api_key)
+            }
+            None => api_key.clone(),
+        };
+        entries.push(serde_json::json!({
+            "id": id,
+            "name": name,
+            "api_key": stored_key,
+        }));
+        save_key_entries(&app, entries)?;
+    }
+    Ok(TinifyKeyEntry {
+        id,
+        name,
+        api_key: mask_api_key(&api_key),
+    })
+}
+
+/// 从设置与安全存储中删除 key
+#[tauri::command]
+pub async fn ipc_tinify_remove_key<R: Runtime>(
+    app: AppHandle<R>,
+    vault: tauri::State<'_, TinifyKeyVault>,
+    id: String,
+) -> std::result::Result<(), TinifyError> {
+    let _edits = vault.lock_edits();
+    let mut entries = get_key_entries(&app)?;
+    entries.retain(|entry| entry_id(entry).as_deref() != Some(id.as_str()));
+    let ids: Vec<String> = entries.iter().filter_map(entry_id).collect();
+    save_key_entries(&app, entries)?;
+    vault.retain(&ids)
+}
+
+/// 界面与 sidecar 按 id 取得明文 key，设置中只保存打码后的值
+#[tauri::command]
+pub async fn ipc_tinify_api_keys<R: Runtime>(
+    app: AppHandle<R>,
+) -> std::result::Result<Vec<TinifyKeyEntry>, TinifyError> {
+    get_key_records(&app)
+}
+
+/// 当前使用的 key 存储后端，为空时 key 以明文保存在设置中
+#[tauri::command]
+pub fn ipc_tinify_key_storage(vault: tauri::State<'_, TinifyKeyVault>) -> Option<SecretBackend> {
+    vault.backend()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_keys_are_not_used_as_plaintext() {
+        let api_key = "abcd1234efgh5678";
+        let entry = |id: Option<String>, api_key: &str| TinifyApiKey {
+            id,
+            name: String::new(),
+            api_key: api_key.to_string(),
+        };
+        assert_eq!(
+            plaintext_key(&entry(None, api_key)).as_deref(),
+            Some(api_key)
+        );
+        assert_eq!(
+            plaintext_key(&entry(Some(key_id(api_key)), api_key)).as_deref(),
+            Some(api_key)
+        );
+        assert_eq!(
+            plaintext_key(&entry(Some(key_id(api_key)), &mask_api_key(api_key))),
+            None
+        );
+        assert_eq!(plaintext_key(&entry(None, " ")), None);
+    }
+
+    #[test]
+    fn converts_civil_dates_to_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 2, 29), 951_782_400 / 86400);
+        assert_eq!(days_from_civil(2024, 3, 1), 1_709_251_200 / 86400);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+    }
+
+    #[test]
+    fn next_month_start_mid_month() {
+        // 2024-03-15 12:30:00 -> 2024-04-01
+        assert_eq!(next_month_start(1_710_505_800), 1_711_929_600);
+        // 2024-01-31 23:59:59 -> 2024-02-01
+        assert_eq!(next_month_start(1_706_745_599), 1_706_745_600);
+    }
+
+    #[test]
+    fn next_month_start_rolls_over_year() {
+        // 2023-12-31 23:59:59 -> 2024-01-01
+        assert_eq!(next_month_start(1_704_067_199), 1_704_067_200);
+        // 月初 0 点属于当月，重置时间为下个月
+        assert_eq!(next_month_start(1_704_067_200), 1_706_745_600);
+    }
+
+    #[test]
+    fn next_month_start_on_leap_day() {
+        // 2024-02-29 00:00:00 与 23:59:59 -> 2024-03-01
+        assert_eq!(next_month_start(1_709_164_800), 1_709_251_200);
+        assert_eq!(next_month_start(1_709_251_199), 1_709_251_200);
+    }
+}
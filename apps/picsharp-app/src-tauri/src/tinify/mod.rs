@@ -0,0 +1,621 @@
+pub mod batch;
+pub mod keys;
+pub mod secret;
+
+use crate::http::HttpConfig;
+use crate::image_processor::compressors::{create_compression_options_from_store, source_roots};
+use crate::remote::{
+    ext_from_content_type, remote_to_output, RemoteCompressor, RemoteOutput, RemoteResult,
+};
+use crate::upload::{
+    download_file, upload_file, Error as UploadError, ProgressPayload, ProgressReporter,
+    UploadResponse,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use keys::{get_api_keys, get_request_interval, KeyRateLimiter, TinifyKeyManager};
+use nanoid::nanoid;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TinifyError {
+    #[error("Upload failed: {0}")]
+    Upload(String),
+    #[error("Download failed: {0}")]
+    Download(String),
+    #[error("No available API key")]
+    NoApiKey,
+    #[error("API key validation failed: {0}")]
+    ApiKeyValidation(String),
+    #[error("TinyPNG API returned error: {0}")]
+    ApiError(String),
+    #[error("Failed to parse JSON response: {0}")]
+    JsonParse(#[from] serde_json::Error),
+    #[error("Store operation failed: {0}")]
+    StoreError(String),
+    #[error("Secure key storage failed: {0}")]
+    SecretStore(String),
+    #[error("File operation failed: {0}")]
+    FileIOError(String),
+}
+
+impl Serialize for TinifyError {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+type Result<T> = std::result::Result<T, TinifyError>;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinifyApiKey {
+    // 已存入安全存储的 key 在设置中只保留打码后的 api_key，按 id 取出明文
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub name: String,
+    pub api_key: String,
+}
+
+/// 下载压缩结果时作为 JSON 请求体发送的选项，未设置的字段不会发送
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TinifyOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preserve: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resize: Option<TinifyResizeOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub convert: Option<TinifyConvertOptions>,
+    // 转换格式时透明区域的背景色
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform: Option<TinifyTransformOptions>,
+}
+
+impl TinifyOptions {
+    pub fn is_empty(&self) -> bool {
+        self.preserve.is_none()
+            && self.resize.is_none()
+            && self.convert.is_none()
+            && self.transform.is_none()
+    }
+
+    // 缩放或转换格式时，即使压缩率不达标也需要使用 TinyPNG 的输出
+    pub fn has_transform(&self) -> bool {
+        self.resize.is_some() || self.convert.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinifyResizeOptions {
+    pub method: String, // "scale", "fit", "cover", "thumb"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinifyConvertOptions {
+    // "image/webp", "image/jpeg", "image/png" 等，指定多个时 TinyPNG 返回体积最小的格式
+    pub r#type: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TinifyTransformOptions {
+    pub background: String, // 转换为JPEG时透明区域的背景色，如 "#FFFFFF"、"white"
+}
+
+fn get_store_setting<R: Runtime, T: serde::de::DeserializeOwned>(
+    store: &tauri_plugin_store::Store<R>,
+    key: &str,
+) -> Option<T> {
+    store.get(key).and_then(|v| serde_json::from_value(v).ok())
+}
+
+// 本地设置中的缩放方式映射为 TinyPNG 的 method，TinyPNG 不支持拉伸，
+// 会裁剪的 fill/outside 按 cover 处理
+fn resize_method(fit: &str) -> &'static str {
+    match fit {
+        "contain" | "inside" => "fit",
+        _ => "cover",
+    }
+}
+
+fn convert_mime_type(format: &str) -> Option<&'static str> {
+    match format {
+        "avif" => Some("image/avif"),
+        "webp" => Some("image/webp"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        _ => None,
+    }
+}
+
+/// 根据设置生成 TinyPNG 的 preserve、resize、convert 选项，convert 只读取 `tinypng_convert`
+pub fn tinify_options_from_store<R: Runtime>(app: &AppHandle<R>) -> Result<TinifyOptions> {
+    let store = app
+        .store("settings.json")
+        .map_err(|e| TinifyError::StoreError(e.to_string()))?;
+    let mut options = TinifyOptions::default();
+
+    let preserve: Vec<String> =
+        get_store_setting(&store, "tinypng_preserve_metadata").unwrap_or_default();
+    if !preserve.is_empty() {
+        options.preserve = Some(preserve);
+    }
+
+    if get_store_setting(&store, "compression_resize_enable").unwrap_or(false) {
+        let [width, height]: [u32; 2] =
+            get_store_setting(&store, "compression_resize_dimensions").unwrap_or([0, 0]);
+        let fit: String = get_store_setting(&store, "compression_resize_fit")
+            .unwrap_or_else(|| "cover".to_string());
+        // 只设置一边时等比缩放，0 表示不限制
+        options.resize = match (width, height) {
+            (0, 0) => None,
+            (width, 0) => Some(TinifyResizeOptions {
+                method: "scale".to_string(),
+                width: Some(width),
+                height: None,
+            }),
+            (0, height) => Some(TinifyResizeOptions {
+                method: "scale".to_string(),
+                width: None,
+                height: Some(height),
+            }),
+            (width, height) => Some(TinifyResizeOptions {
+                method: resize_method(&fit).to_string(),
+                width: Some(width),
+                height: Some(height),
+            }),
+        };
+    }
+
+    // 本地引擎的 compression_convert 会在压缩结果之外额外生成转换后的文件，
+    // 而 TinyPNG 的 convert 会用列出格式中体积最小的一个替换压缩结果，
+    // 因此使用单独的 tinypng_convert 设置，不沿用本地的格式转换设置
+    let formats: Vec<String> = get_store_setting(&store, "tinypng_convert").unwrap_or_default();
+    let types: Vec<String> = formats
+        .iter()
+        .filter_map(|format| convert_mime_type(format))
+        .map(String::from)
+        .collect();
+    if !types.is_empty() {
+        options.convert = Some(TinifyConvertOptions { r#type: types });
+        let background: String =
+            get_store_setting(&store, "compression_convert_alpha").unwrap_or_default();
+        if !background.is_empty() {
+            options.transform = Some(TinifyTransformOptions { background });
+        }
+    }
+
+    Ok(options)
+}
+
+pub const DEFAULT_BASE_URL: &str = "https://api.tinify.com";
+
+/// shrink 接口地址，`tinypng_base_url` 可指向兼容的代理网关或本地的模拟服务
+pub fn shrink_url<R: Runtime>(app: &AppHandle<R>) -> String {
+    let base_url = app
+        .store("settings.json")
+        .ok()
+        .and_then(|store| store.get("tinypng_base_url"))
+        .and_then(|v| {
+            v.as_str()
+                .map(|s| s.trim().trim_end_matches('/').to_string())
+        })
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+    format!("{}/shrink", base_url)
+}
+
+// TinyPNG 支持压缩的文件扩展名
+pub const TINIFY_SUPPORTED_EXTS: [&str; 5] = ["png", "jpg", "jpeg", "webp", "avif"];
+
+// 从 TinyPNG 的错误响应中取出 message
+fn api_error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|json| {
+            json.get("message")
+                .and_then(|m| m.as_str())
+                .map(String::from)
+        })
+        .unwrap_or_else(|| body.to_string())
+}
+
+/// 调用 shrink 接口所需的设置与 key 状态
+struct ShrinkClient<'a> {
+    url: String,
+    http: HttpConfig,
+    interval: Option<Duration>,
+    manager: &'a TinifyKeyManager,
+    limiter: &'a KeyRateLimiter,
+}
+
+impl<'a> ShrinkClient<'a> {
+    fn from_app<R: Runtime>(app: &'a AppHandle<R>) -> Self {
+        Self {
+            url: shrink_url(app),
+            http: HttpConfig::from_store(app),
+            interval: get_request_interval(app),
+            manager: app.state::<TinifyKeyManager>().inner(),
+            limiter: app.state::<KeyRateLimiter>().inner(),
+        }
+    }
+
+    /// 上传图片到 shrink 接口，key 无效（401）或额度用完（429）时换用下一个 key
+    async fn shrink(
+        &self,
+        api_keys: &[String],
+        input_path: &str,
+        mime: &str,
+        progress: Option<&ProgressReporter>,
+    ) -> Result<(String, UploadResponse)> {
+        let (manager, limiter) = (self.manager, self.limiter);
+        let mut tried = Vec::new();
+
+        loop {
+            let api_key = manager.acquire(api_keys, &tried)?;
+            let mut headers = HashMap::new();
+            headers.insert(
+                "Authorization".to_string(),
+                format!("Basic {}", STANDARD.encode(format!("api:{}", api_key))),
+            );
+            headers.insert("Content-Type".to_string(), mime.to_string());
+            if let Some(interval) = self.interval {
+                limiter.wait(&api_key, interval).await;
+            }
+
+            match upload_file(&self.url, input_path, "POST", headers, &self.http, progress).await {
+                Ok(response) => {
+                    let compression_count = response
+                        .headers
+                        .get("Compression-Count")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.trim().parse::<u64>().ok());
+                    manager.record_success(&api_key, compression_count);
+                    return Ok((api_key, response));
+                }
+                Err(UploadError::HttpErrorCode(401, body)) => {
+                    manager.mark_invalid(&api_key, &api_error_message(&body));
+                }
+                Err(UploadError::HttpErrorCode(429, body)) => {
+                    manager.mark_exhausted(&api_key, &api_error_message(&body));
+                }
+                Err(e) => return Err(TinifyError::Upload(e.to_string())),
+            }
+            tried.push(api_key);
+        }
+    }
+}
+
+async fn shrink_with_failover<R: Runtime>(
+    app: &AppHandle<R>,
+    input_path: &str,
+    mime: &str,
+    progress: Option<&ProgressReporter>,
+) -> Result<(String, UploadResponse)> {
+    let api_keys = get_api_keys(app)?;
+    if api_keys.is_empty() {
+        return Err(TinifyError::NoApiKey);
+    }
+    ShrinkClient::from_app(app)
+        .shrink(&api_keys, input_path, mime, progress)
+        .await
+}
+
+/// TinyPNG 远程压缩服务
+pub struct TinifyCompressor<R: Runtime> {
+    app: AppHandle<R>,
+    options: TinifyOptions,
+    progress: Option<ProgressReporter>,
+}
+
+impl<R: Runtime> TinifyCompressor<R> {
+    pub fn new(app: AppHandle<R>, options: TinifyOptions) -> Self {
+        Self {
+            app,
+            options,
+            progress: None,
+        }
+    }
+
+    /// 上传与下载时通过 progress 发送传输进度
+    pub fn with_progress(mut self, progress: Option<ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    pub fn from_app(app: &AppHandle<R>) -> Result<Self> {
+        Ok(Self::new(app.clone(), tinify_options_from_store(app)?))
+    }
+
+    async fn shrink_to(
+        &self,
+        input_path: &Path,
+        mime: &str,
+        temp_path: &Path,
+    ) -> Result<RemoteOutput> {
+        let input_path_str = input_path.to_string_lossy();
+        let (api_key, response) =
+            shrink_with_failover(&self.app, &input_path_str, mime, self.progress.as_ref()).await?;
+        let api_key_base64 = STANDARD.encode(format!("api:{}", api_key));
+
+        // 解析上传响应
+        let response_json: Value = serde_json::from_str(&response.body)?;
+
+        // 检查是否有错误
+        if let Some(error) = response_json.get("error") {
+            let message = error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .unwrap_or("unknown error");
+            return Err(TinifyError::ApiError(message.to_string()));
+        }
+
+        let original_size = response_json
+            .get("input")
+            .and_then(|o| o.get("size"))
+            .and_then(|s| s.as_u64())
+            .unwrap_or(0);
+
+        let compressed_size = response_json
+            .get("output")
+            .and_then(|o| o.get("size"))
+            .and_then(|s| s.as_u64())
+            .unwrap_or(0);
+
+        let transformed = self.options.has_transform();
+
+        // 没有变小且不需要缩放或转换时不必下载，直接使用原图
+        if compressed_size >= original_size && !transformed {
+            tokio::fs::copy(input_path, temp_path)
+                .await
+                .map_err(|e| TinifyError::FileIOError(e.to_string()))?;
+            return Ok(RemoteOutput {
+                ext: None,
+                transformed,
+            });
+        }
+
+        // 获取压缩后图片的URL
+        let output_url = if let Some(url) = response_json
+            .get("output")
+            .and_then(|o| o.get("url"))
+            .and_then(|u| u.as_str())
+        {
+            url.to_string()
+        } else {
+            return Err(TinifyError::ApiError(
+                "Cannot get the compressed image URL".to_string(),
+            ));
+        };
+
+        let mut download_headers = HashMap::new();
+        download_headers.insert("Content-Type".to_string(), "application/json".to_string());
+        download_headers.insert(
+            "Authorization".to_string(),
+            format!("Basic {}", api_key_base64),
+        );
+
+        // 有选项时以 JSON 请求体 POST，否则直接 GET 输出地址
+        let body = if self.options.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&self.options)?)
+        };
+
+        let response_headers = download_file(
+            &output_url,
+            &temp_path.to_string_lossy(),
+            download_headers,
+            body,
+            &HttpConfig::from_store(&self.app),
+            self.progress.as_ref(),
+        )
+        .await
+        .map_err(|e| TinifyError::Download(e.to_string()))?;
+
+        Ok(RemoteOutput {
+            ext: ext_from_content_type(&response_headers),
+            transformed,
+        })
+    }
+}
+
+impl<R: Runtime> RemoteCompressor for TinifyCompressor<R> {
+    fn name(&self) -> &'static str {
+        "tinify"
+    }
+
+    fn supports(&self, ext: &str) -> bool {
+        TINIFY_SUPPORTED_EXTS.contains(&ext)
+    }
+
+    async fn compress(
+        &self,
+        input_path: &Path,
+        mime: &str,
+        temp_path: &Path,
+    ) -> std::result::Result<RemoteOutput, String> {
+        self.shrink_to(input_path, mime, temp_path)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[tauri::command]
+pub async fn ipc_tinify<R: Runtime>(
+    app: AppHandle<R>,
+    input_path: String,
+    roots: Option<Vec<String>>,
+    job_id: Option<String>,
+    request_id: Option<String>,
+    on_progress: Channel<ProgressPayload>,
+) -> std::result::Result<RemoteResult, String> {
+    // 校验输入文件是否存在和可访问
+    let input_file = Path::new(&input_path);
+    if !input_file.exists() {
+        return Err(format!("File not found"));
+    }
+
+    // 检查文件可读性
+    match std::fs::metadata(&input_path) {
+        Ok(metadata) => {
+            if !metadata.is_file() {
+                return Err(format!("Path is not a file"));
+            }
+            // 文件存在且是一个文件
+        }
+        Err(_) => {
+            return Err(format!("Cannot access input file"));
+        }
+    }
+
+    // 保留元数据、缩放和格式转换选项
+    let progress = ProgressReporter::new(
+        request_id.unwrap_or_else(|| input_path.clone()),
+        on_progress,
+    );
+    let compressor = TinifyCompressor::from_app(&app)
+        .map_err(|e| e.to_string())?
+        .with_progress(Some(progress));
+
+    // 与本地引擎共用输出路径与冲突策略
+    let mut options = create_compression_options_from_store(&app)?;
+    options.source_roots = source_roots(roots);
+    remote_to_output(
+        &compressor,
+        &app,
+        &input_path,
+        &job_id.unwrap_or_else(|| nanoid!()),
+        &options,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::keys::KeyState;
+    use super::*;
+    use crate::http::RetryPolicy;
+    use crate::test_support::{temp_file, StubResponse, StubServer};
+
+    const KEYS: [&str; 3] = ["key-invalid", "key-exhausted", "key-valid"];
+
+    fn authorization(api_key: &str) -> String {
+        format!("Basic {}", STANDARD.encode(format!("api:{}", api_key)))
+    }
+
+    // 按 Authorization 区分 key：无效 401、额度用完 429、其余成功
+    fn tinify_stub() -> StubServer {
+        StubServer::start(|request| {
+            let auth = request.header("Authorization").unwrap_or_default();
+            if auth == authorization(KEYS[0]) {
+                StubResponse::new(401)
+                    .body(r#"{"error":"Unauthorized","message":"Credentials are invalid."}"#)
+            } else if auth == authorization(KEYS[1]) {
+                StubResponse::new(429).body(r#"{"error":"TooManyRequests","message":"Your monthly limit has been exceeded."}"#)
+            } else {
+                StubResponse::new(201)
+                    .header("Compression-Count", "42")
+                    .body(r#"{"input":{"size":8},"output":{"size":4,"url":"/output/1"}}"#)
+            }
+        })
+    }
+
+    // 用量越少越先被选中，让 key 按 KEYS 的顺序被尝试
+    fn manager_in_order(api_keys: &[String]) -> TinifyKeyManager {
+        let manager = TinifyKeyManager::default();
+        for (count, api_key) in api_keys.iter().enumerate() {
+            manager.record_success(api_key, Some(count as u64));
+        }
+        manager
+    }
+
+    async fn shrink(
+        server: &StubServer,
+        manager: &TinifyKeyManager,
+        api_keys: &[String],
+    ) -> Result<(String, UploadResponse)> {
+        let limiter = KeyRateLimiter::default();
+        let client = ShrinkClient {
+            url: format!("{}/shrink", server.url),
+            http: HttpConfig {
+                retry: RetryPolicy {
+                    base_delay: Duration::from_millis(1),
+                    ..RetryPolicy::default()
+                },
+                ..HttpConfig::default()
+            },
+            interval: None,
+            manager,
+            limiter: &limiter,
+        };
+        let input_path = temp_file("png", b"original");
+        let result = client
+            .shrink(api_keys, &input_path.to_string_lossy(), "image/png", None)
+            .await;
+        let _ = std::fs::remove_file(&input_path);
+        result
+    }
+
+    fn state(manager: &TinifyKeyManager, api_key: &str) -> KeyState {
+        manager.status(&[api_key.to_string()], 500)[0].state
+    }
+
+    #[tokio::test]
+    async fn fails_over_invalid_and_exhausted_keys() {
+        let server = tinify_stub();
+        let api_keys: Vec<String> = KEYS.iter().map(|key| key.to_string()).collect();
+        let manager = manager_in_order(&api_keys);
+
+        let (api_key, response) = shrink(&server, &manager, &api_keys).await.unwrap();
+        assert_eq!(api_key, KEYS[2]);
+        assert!(response.body.contains("/output/1"));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|request| request.path == "/shrink"));
+        assert_eq!(state(&manager, KEYS[0]), KeyState::Invalid);
+        assert_eq!(state(&manager, KEYS[1]), KeyState::Exhausted);
+        assert_eq!(state(&manager, KEYS[2]), KeyState::Active);
+        let status = manager.status(&[KEYS[2].to_string()], 500);
+        assert_eq!(status[0].compression_count, Some(42));
+    }
+
+    #[tokio::test]
+    async fn skips_keys_already_marked_unusable() {
+        let server = tinify_stub();
+        let api_keys: Vec<String> = KEYS.iter().map(|key| key.to_string()).collect();
+        let manager = manager_in_order(&api_keys);
+        manager.mark_invalid(KEYS[0], "invalid");
+        manager.mark_exhausted(KEYS[1], "exhausted");
+
+        let (api_key, _) = shrink(&server, &manager, &api_keys).await.unwrap();
+        assert_eq!(api_key, KEYS[2]);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn returns_no_api_key_when_every_key_fails() {
+        let server = tinify_stub();
+        let api_keys: Vec<String> = KEYS[..2].iter().map(|key| key.to_string()).collect();
+        let manager = manager_in_order(&api_keys);
+
+        let result = shrink(&server, &manager, &api_keys).await;
+        assert!(matches!(result, Err(TinifyError::NoApiKey)));
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(state(&manager, KEYS[0]), KeyState::Invalid);
+        assert_eq!(state(&manager, KEYS[1]), KeyState::Exhausted);
+    }
+}
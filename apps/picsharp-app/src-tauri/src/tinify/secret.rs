@@ -0,0 +1,522 @@
+use super::{Result, TinifyError};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use log::{error, warn};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+
+const KEYRING_SERVICE: &str = "PicSharp";
+const KEYRING_ACCOUNT: &str = "tinypng_api_keys";
+const ENCRYPTED_FILE_NAME: &str = "tinify_keys.enc";
+// 加密文件的口令，设置后优先使用加密文件，适用于没有系统钥匙串的无界面 Linux
+pub const PASSPHRASE_ENV: &str = "PICSHARP_KEYSTORE_PASSPHRASE";
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const AAD: &[u8] = b"picsharp-tinify-keys";
+
+/// key 的标识（sha256 前 16 位十六进制），设置与用量记录中用它代替明文 key
+pub fn key_id(api_key: &str) -> String {
+    let hash = Sha256::digest(api_key.trim().as_bytes());
+    hash[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn is_key_id(value: &str) -> bool {
+    value.len() == 16
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+/// 只保留前后 4 位，用于界面显示
+pub fn mask_api_key(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.trim().chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecretBackend {
+    // macOS 钥匙串、Windows 凭据管理器或 Linux Secret Service
+    #[serde(rename = "keyring")]
+    Keyring,
+    // 使用口令派生密钥加密的 `app_data_dir/tinify_keys.enc`
+    #[serde(rename = "encrypted_file")]
+    EncryptedFile,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey> {
+    let iterations = NonZeroU32::new(iterations)
+        .ok_or_else(|| TinifyError::SecretStore("Invalid key file iterations".to_string()))?;
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&CHACHA20_POLY1305, &key)
+        .map_err(|_| TinifyError::SecretStore("Failed to create cipher key".to_string()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn decode_field(value: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| TinifyError::SecretStore(format!("Corrupted key file: {}", e)))
+}
+
+fn encrypt(passphrase: &str, data: &[u8]) -> Result<EncryptedFile> {
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt)
+        .and_then(|_| rng.fill(&mut nonce))
+        .map_err(|_| TinifyError::SecretStore("Failed to generate random bytes".to_string()))?;
+
+    let key = derive_key(passphrase, &salt, PBKDF2_ITERATIONS)?;
+    let mut in_out = data.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::from(AAD),
+        &mut in_out,
+    )
+    .map_err(|_| TinifyError::SecretStore("Failed to encrypt API keys".to_string()))?;
+
+    Ok(EncryptedFile {
+        version: 1,
+        iterations: PBKDF2_ITERATIONS,
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(in_out),
+    })
+}
+
+fn decrypt(passphrase: &str, file: &EncryptedFile) -> Result<Vec<u8>> {
+    let salt = decode_field(&file.salt)?;
+    let nonce = Nonce::try_assume_unique_for_key(&decode_field(&file.nonce)?)
+        .map_err(|_| TinifyError::SecretStore("Corrupted key file: invalid nonce".to_string()))?;
+    let mut in_out = decode_field(&file.ciphertext)?;
+    let key = derive_key(passphrase, &salt, file.iterations)?;
+    let plain = key
+        .open_in_place(nonce, Aad::from(AAD), &mut in_out)
+        .map_err(|_| {
+            TinifyError::SecretStore("Wrong passphrase or corrupted key file".to_string())
+        })?;
+    Ok(plain.to_vec())
+}
+
+#[cfg(target_os = "macos")]
+mod keyring {
+    use super::{KEYRING_ACCOUNT, KEYRING_SERVICE};
+    use security_framework::passwords::{get_generic_password, set_generic_password};
+
+    const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+    pub fn get() -> Result<Option<Vec<u8>>, String> {
+        match get_generic_password(KEYRING_SERVICE, KEYRING_ACCOUNT) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn set(data: &[u8]) -> Result<(), String> {
+        set_generic_password(KEYRING_SERVICE, KEYRING_ACCOUNT, data).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(windows)]
+mod keyring {
+    use super::{KEYRING_ACCOUNT, KEYRING_SERVICE};
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_NOT_FOUND};
+    use windows_sys::Win32::Security::Credentials::{
+        CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    // 凭据管理器单个凭据最大 5 * 512 字节
+    const MAX_BLOB_SIZE: usize = 2560;
+
+    fn wide(value: &str) -> Vec<u16> {
+        value.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn target_name() -> Vec<u16> {
+        wide(&format!("{}/{}", KEYRING_SERVICE, KEYRING_ACCOUNT))
+    }
+
+    pub fn get() -> Result<Option<Vec<u8>>, String> {
+        let target = target_name();
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        // SAFETY: target 以 0 结尾，读取成功后 credential 指向系统分配的内存，复制后由 CredFree 释放
+        unsafe {
+            if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+                let code = GetLastError();
+                if code == ERROR_NOT_FOUND {
+                    return Ok(None);
+                }
+                return Err(format!("CredReadW failed with error {}", code));
+            }
+            let data = std::slice::from_raw_parts(
+                (*credential).CredentialBlob,
+                (*credential).CredentialBlobSize as usize,
+            )
+            .to_vec();
+            CredFree(credential as *const _);
+            Ok(Some(data))
+        }
+    }
+
+    pub fn set(data: &[u8]) -> Result<(), String> {
+        if data.len() > MAX_BLOB_SIZE {
+            return Err("Too many API keys for Windows Credential Manager".to_string());
+        }
+        let mut target = target_name();
+        let mut user_name = wide(KEYRING_ACCOUNT);
+        // SAFETY: 结构体中的指针在调用期间有效，CredWriteW 会复制数据
+        unsafe {
+            let mut credential: CREDENTIALW = std::mem::zeroed();
+            credential.Type = CRED_TYPE_GENERIC;
+            credential.TargetName = target.as_mut_ptr();
+            credential.UserName = user_name.as_mut_ptr();
+            credential.CredentialBlobSize = data.len() as u32;
+            credential.CredentialBlob = data.as_ptr() as *mut u8;
+            credential.Persist = CRED_PERSIST_LOCAL_MACHINE;
+            if CredWriteW(&credential, 0) == 0 {
+                return Err(format!("CredWriteW failed with error {}", GetLastError()));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 通过 libsecret 的 secret-tool 访问 Secret Service（GNOME Keyring、KWallet 等）
+#[cfg(target_os = "linux")]
+mod keyring {
+    use super::{KEYRING_ACCOUNT, KEYRING_SERVICE};
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    fn available() -> Result<(), String> {
+        // 没有会话总线时 secret-tool 无法连接 Secret Service
+        if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_none() {
+            return Err("D-Bus session bus is not available".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn get() -> Result<Option<Vec<u8>>, String> {
+        available()?;
+        let output = Command::new("secret-tool")
+            .args([
+                "lookup",
+                "service",
+                KEYRING_SERVICE,
+                "account",
+                KEYRING_ACCOUNT,
+            ])
+            .output()
+            .map_err(|e| format!("Failed to run secret-tool: {}", e))?;
+        if output.status.success() {
+            return Ok(Some(output.stdout));
+        }
+        // 未找到时退出码非 0 且没有错误输出
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.trim().is_empty() {
+            Ok(None)
+        } else {
+            Err(stderr.trim().to_string())
+        }
+    }
+
+    pub fn set(data: &[u8]) -> Result<(), String> {
+        available()?;
+        let mut child = Command::new("secret-tool")
+            .args([
+                "store",
+                "--label=PicSharp TinyPNG API keys",
+                "service",
+                KEYRING_SERVICE,
+                "account",
+                KEYRING_ACCOUNT,
+            ])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run secret-tool: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(data).map_err(|e| e.to_string())?;
+        }
+        let output = child.wait_with_output().map_err(|e| e.to_string())?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+mod keyring {
+    pub fn get() -> Result<Option<Vec<u8>>, String> {
+        Err("OS keyring is not supported on this platform".to_string())
+    }
+
+    pub fn set(_data: &[u8]) -> Result<(), String> {
+        Err("OS keyring is not supported on this platform".to_string())
+    }
+}
+
+enum SecretStore {
+    Keyring,
+    EncryptedFile { path: PathBuf, passphrase: String },
+}
+
+impl SecretStore {
+    fn backend(&self) -> SecretBackend {
+        match self {
+            Self::Keyring => SecretBackend::Keyring,
+            Self::EncryptedFile { .. } => SecretBackend::EncryptedFile,
+        }
+    }
+
+    fn read(&self) -> Result<Option<Vec<u8>>> {
+        match self {
+            Self::Keyring => keyring::get().map_err(TinifyError::SecretStore),
+            Self::EncryptedFile { path, passphrase } => {
+                if !path.exists() {
+                    return Ok(None);
+                }
+                let data = fs::read(path).map_err(|e| TinifyError::FileIOError(e.to_string()))?;
+                let file: EncryptedFile = serde_json::from_slice(&data)?;
+                decrypt(passphrase, &file).map(Some)
+            }
+        }
+    }
+
+    fn write(&self, data: &[u8]) -> Result<()> {
+        match self {
+            Self::Keyring => keyring::set(data).map_err(TinifyError::SecretStore),
+            Self::EncryptedFile { path, passphrase } => {
+                let file = encrypt(passphrase, data)?;
+                // 先写临时文件再重命名，避免写入中断后丢失全部 key
+                let temp_path = path.with_extension("enc.tmp");
+                path.parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(&temp_path, serde_json::to_vec_pretty(&file)?))
+                    .and_then(|_| restrict_permissions(&temp_path))
+                    .and_then(|_| fs::rename(&temp_path, path))
+                    .map_err(|e| TinifyError::FileIOError(e.to_string()))
+            }
+        }
+    }
+}
+
+// 加密文件仅当前用户可读写
+#[cfg(unix)]
+fn restrict_permissions(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &std::path::Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// 按 key id 保存明文 key 的安全存储，读取后缓存在内存中。
+/// 由设置 `tinypng_key_storage` 选择后端："keyring"、"file" 或 "auto"（默认）。
+/// "auto" 在设置了 `PICSHARP_KEYSTORE_PASSPHRASE` 时使用加密文件，否则使用系统钥匙串；
+/// 两者都不可用时没有安全存储，key 仍保留在设置中。
+#[derive(Default)]
+pub struct TinifyKeyVault {
+    store: Option<SecretStore>,
+    keys: Mutex<Option<BTreeMap<String, String>>>,
+    edits: Mutex<()>,
+}
+
+impl TinifyKeyVault {
+    pub fn from_app<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let storage = app
+            .store("settings.json")
+            .ok()
+            .and_then(|store| store.get("tinypng_key_storage"))
+            .and_then(|v| v.as_str().map(String::from))
+            .unwrap_or_else(|| "auto".to_string());
+        let passphrase = std::env::var(PASSPHRASE_ENV)
+            .ok()
+            .filter(|passphrase| !passphrase.is_empty());
+        let encrypted_file = |passphrase: String| {
+            app.path()
+                .app_data_dir()
+                .map(|dir| SecretStore::EncryptedFile {
+                    path: dir.join(ENCRYPTED_FILE_NAME),
+                    passphrase,
+                })
+                .map_err(|e| error!("[TinifyKeyVault] -> Failed to get app data dir: {}", e))
+                .ok()
+        };
+
+        let store = match (storage.as_str(), passphrase) {
+            ("keyring", _) => Some(SecretStore::Keyring),
+            ("file", None) => {
+                error!(
+                    "[TinifyKeyVault] -> {} is required for encrypted file storage",
+                    PASSPHRASE_ENV
+                );
+                None
+            }
+            (_, Some(passphrase)) => encrypted_file(passphrase),
+            _ => match keyring::get() {
+                Ok(_) => Some(SecretStore::Keyring),
+                Err(e) => {
+                    warn!(
+                        "[TinifyKeyVault] -> OS keyring unavailable, set {} to use an encrypted file: {}",
+                        PASSPHRASE_ENV, e
+                    );
+                    None
+                }
+            },
+        };
+        Self {
+            store,
+            keys: Mutex::new(None),
+            edits: Mutex::new(()),
+        }
+    }
+
+    /// 修改设置中的 key 列表并同步安全存储时持有，避免并发修改时误删刚添加的 key
+    pub fn lock_edits(&self) -> MutexGuard<'_, ()> {
+        self.edits.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 当前使用的存储后端，为空时 key 仍以明文保存在设置中
+    pub fn backend(&self) -> Option<SecretBackend> {
+        self.store.as_ref().map(SecretStore::backend)
+    }
+
+    fn with_keys<T>(&self, f: impl FnOnce(&mut BTreeMap<String, String>) -> T) -> Result<T> {
+        let store = self.store.as_ref().ok_or_else(|| {
+            TinifyError::SecretStore("No secure key storage available".to_string())
+        })?;
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        if keys.is_none() {
+            let loaded = match store.read()? {
+                Some(data) => serde_json::from_slice(&data)?,
+                None => BTreeMap::new(),
+            };
+            *keys = Some(loaded);
+        }
+        Ok(f(keys.get_or_insert_with(BTreeMap::new)))
+    }
+
+    fn save(&self, keys: &BTreeMap<String, String>) -> Result<()> {
+        match &self.store {
+            Some(store) => store.write(&serde_json::to_vec(keys)?),
+            None => Ok(()),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<String>> {
+        self.with_keys(|keys| keys.get(id).cloned())
+    }
+
+    /// 保存 key 并返回其 id
+    pub fn insert(&self, api_key: &str) -> Result<String> {
+        let api_key = api_key.trim().to_string();
+        let id = key_id(&api_key);
+        let (changed, snapshot) = self.with_keys(|keys| {
+            let changed = keys.get(&id) != Some(&api_key);
+            keys.insert(id.clone(), api_key);
+            (changed, keys.clone())
+        })?;
+        if changed {
+            self.save(&snapshot)?;
+        }
+        Ok(id)
+    }
+
+    /// 删除不在 `ids` 中的 key
+    pub fn retain(&self, ids: &[String]) -> Result<()> {
+        if self.store.is_none() {
+            return Ok(());
+        }
+        let (changed, snapshot) = self.with_keys(|keys| {
+            let len = keys.len();
+            keys.retain(|id, _| ids.contains(id));
+            (keys.len() != len, keys.clone())
+        })?;
+        if changed {
+            self.save(&snapshot)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn encrypted_file_round_trip() {
+        let dir = temp_dir();
+        let path = dir.join(ENCRYPTED_FILE_NAME);
+        let store = SecretStore::EncryptedFile {
+            path: path.clone(),
+            passphrase: "correct horse".to_string(),
+        };
+        assert!(store.read().unwrap().is_none());
+
+        let data = br#"{"0123456789abcdef":"secret-key"}"#;
+        store.write(data).unwrap();
+        // 文件中不含明文，且每次加密使用新的 salt 与 nonce
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("secret-key"));
+        assert_eq!(store.read().unwrap().as_deref(), Some(&data[..]));
+
+        store.write(data).unwrap();
+        assert_ne!(fs::read_to_string(&path).unwrap(), raw);
+        assert_eq!(store.read().unwrap().as_deref(), Some(&data[..]));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn wrong_passphrase_fails_to_decrypt() {
+        let file = encrypt("correct horse", b"secret-key").unwrap();
+        assert_eq!(decrypt("correct horse", &file).unwrap(), b"secret-key");
+        let err = decrypt("battery staple", &file).unwrap_err();
+        assert!(err.to_string().contains("Wrong passphrase"));
+
+        // 密文被篡改时同样无法解密
+        let mut ciphertext = decode_field(&file.ciphertext).unwrap();
+        ciphertext[0] ^= 1;
+        let tampered = EncryptedFile {
+            ciphertext: STANDARD.encode(ciphertext),
+            ..file
+        };
+        assert!(decrypt("correct horse", &tampered).is_err());
+    }
+}
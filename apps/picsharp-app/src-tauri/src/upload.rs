@@ -7,8 +7,11 @@ use tokio::{
 };
 use tokio_util::codec::{BytesCodec, FramedRead};
 
+use crate::http::{send_with_retry, HttpConfig};
+use log::warn;
 use std::collections::HashMap;
 use std::time::Instant;
+use tauri::ipc::Channel;
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -34,8 +37,8 @@ impl TransferStats {
             granularity,
         }
     }
-    // 记录数据块的传输并更新传输速度和总进度。
-    pub fn record_chunk_transfer(&mut self, chunk_len: usize) {
+    // 记录数据块的传输并更新传输速度和总进度，重新计算了传输速度时返回 true。
+    pub fn record_chunk_transfer(&mut self, chunk_len: usize) -> bool {
         let now = Instant::now();
         let it_took = now.duration_since(self.start_time).as_millis();
         self.accumulated_chunk_len += chunk_len;
@@ -43,15 +46,17 @@ impl TransferStats {
         self.accumulated_time += it_took;
 
         // 如果累计时间超过粒度，则计算传输速度。
-        if self.accumulated_time >= self.granularity as u128 {
+        let updated = self.accumulated_time >= self.granularity as u128;
+        if updated {
             self.transfer_speed =
-                (self.accumulated_chunk_len as u128 / self.accumulated_time * 1024) as u64;
+                (self.accumulated_chunk_len as u128 * 1000 / self.accumulated_time) as u64;
             self.accumulated_chunk_len = 0;
             self.accumulated_time = 0;
         }
 
         // 重置下一周期的开始时间。
         self.start_time = now;
+        updated
     }
 }
 
@@ -68,6 +73,8 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Request(#[from] reqwest::Error),
+    #[error(transparent)]
+    Http(#[from] crate::http::HttpError),
     #[error("{0}")]
     ContentLength(String),
     #[error("request failed with status code {0}: {1}")]
@@ -83,81 +90,163 @@ impl Serialize for Error {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferKind {
+    Upload,
+    Download,
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProgressPayload {
+    // 发起传输时前端指定的请求 id
+    id: String,
+    kind: TransferKind,
+    // 已传输的字节数
     progress: u64,
+    // 总字节数，未知时为 0
     total: u64,
+    // 字节/秒
     transfer_speed: u64,
 }
 
+/// 向前端 Channel 发送传输进度，同一 Channel 可承载多个请求，按 id 区分
+#[derive(Clone)]
+pub struct ProgressReporter {
+    id: String,
+    channel: Channel<ProgressPayload>,
+}
+
+impl ProgressReporter {
+    pub fn new(id: impl Into<String>, channel: Channel<ProgressPayload>) -> Self {
+        Self {
+            id: id.into(),
+            channel,
+        }
+    }
+
+    fn report(&self, kind: TransferKind, stats: &TransferStats, total: u64) {
+        let payload = ProgressPayload {
+            id: self.id.clone(),
+            kind,
+            progress: stats.total_transferred,
+            total,
+            transfer_speed: stats.transfer_speed,
+        };
+        if let Err(e) = self.channel.send(payload) {
+            warn!("Failed to send transfer progress: {}", e);
+        }
+    }
+}
+
 pub async fn download_file(
     url: &str,
     file_path: &str,
     headers: HashMap<String, String>,
     body: Option<String>,
-) -> Result<()> {
-    let client = reqwest::Client::new();
-    let mut request = if let Some(body) = body {
-        client.post(url).body(body)
-    } else {
-        client.get(url)
-    };
-    // 遍历 headers 的键值对
-    // 并将它们添加到请求对象中。
-    for (key, value) in headers {
-        request = request.header(&key, value);
-    }
-
-    let response = request.send().await?;
+    config: &HttpConfig,
+    progress: Option<&ProgressReporter>,
+) -> Result<reqwest::header::HeaderMap> {
+    let client = config.client()?;
+    // 带请求体的 POST 可能触发计费的转换，不按幂等请求重试
+    let response = send_with_retry(&config.retry, body.is_none(), || {
+        let mut request = if let Some(body) = &body {
+            client.post(url).body(body.clone())
+        } else {
+            client.get(url)
+        };
+        // 遍历 headers 的键值对
+        // 并将它们添加到请求对象中。
+        for (key, value) in &headers {
+            request = request.header(key, value);
+        }
+        async { Ok::<_, Error>(request) }
+    })
+    .await?;
     if !response.status().is_success() {
         return Err(Error::HttpErrorCode(
             response.status().as_u16(),
             response.text().await.unwrap_or_default(),
         ));
     }
-    let total = response.content_length().unwrap_or(0);
+    let response_headers = response.headers().clone();
+    save_response(response, file_path, progress).await?;
+    Ok(response_headers)
+}
 
+/// 将响应体流式写入文件，按统计周期发送下载进度
+pub async fn save_response(
+    response: reqwest::Response,
+    file_path: &str,
+    progress: Option<&ProgressReporter>,
+) -> Result<()> {
+    let total = response.content_length().unwrap_or(0);
     let mut file = BufWriter::new(File::create(file_path).await?);
     let mut stream = response.bytes_stream();
 
     let mut stats = TransferStats::default();
     while let Some(chunk) = stream.try_next().await? {
         file.write_all(&chunk).await?;
-        stats.record_chunk_transfer(chunk.len());
+        // 按统计周期发送进度，避免大文件产生过多消息
+        if stats.record_chunk_transfer(chunk.len()) {
+            if let Some(progress) = progress {
+                progress.report(TransferKind::Download, &stats, total);
+            }
+        }
     }
     file.flush().await?;
-
+    if let Some(progress) = progress {
+        progress.report(
+            TransferKind::Download,
+            &stats,
+            total.max(stats.total_transferred),
+        );
+    }
     Ok(())
 }
 
+pub struct UploadResponse {
+    pub headers: reqwest::header::HeaderMap,
+    pub body: String,
+}
+
 pub async fn upload_file(
     url: &str,
     file_path: &str,
     method: &str,
     headers: HashMap<String, String>,
-) -> Result<String> {
-    let file = File::open(file_path).await?;
-    let file_len = file.metadata().await.unwrap().len();
-
-    let client = reqwest::Client::new();
-    let mut request = match method.to_uppercase().as_str() {
-        "POST" => client.post(url),
-        "PUT" => client.put(url),
+    config: &HttpConfig,
+    progress: Option<&ProgressReporter>,
+) -> Result<UploadResponse> {
+    let client = config.client()?;
+    let method = match method.to_uppercase().as_str() {
+        "POST" => reqwest::Method::POST,
+        "PUT" => reqwest::Method::PUT,
         _ => return Err(Error::ContentLength("Invalid HTTP method".into())),
     };
 
-    request = request
-        .header(reqwest::header::CONTENT_LENGTH, file_len)
-        .body(file_to_body(file));
-
-    for (key, value) in headers {
-        request = request.header(&key, value);
-    }
+    // 每次重试都重新打开文件
+    let response = send_with_retry(&config.retry, method.is_idempotent(), || async {
+        let file = File::open(file_path).await?;
+        let file_len = file.metadata().await?.len();
+        let mut request = client
+            .request(method.clone(), url)
+            .header(reqwest::header::CONTENT_LENGTH, file_len)
+            .body(file_to_body(file, file_len, progress.cloned()));
+        for (key, value) in &headers {
+            request = request.header(key, value);
+        }
+        Ok::<_, Error>(request)
+    })
+    .await?;
 
-    let response = request.send().await?;
     if response.status().is_success() {
-        response.text().await.map_err(Into::into)
+        let headers = response.headers().clone();
+        Ok(UploadResponse {
+            headers,
+            body: response.text().await?,
+        })
     } else {
         Err(Error::HttpErrorCode(
             response.status().as_u16(),
@@ -166,14 +255,66 @@ pub async fn upload_file(
     }
 }
 
-fn file_to_body(file: File) -> reqwest::Body {
+/// 以流的形式上传文件，按统计周期发送上传进度
+pub fn file_to_body(file: File, total: u64, progress: Option<ProgressReporter>) -> reqwest::Body {
     let stream = FramedRead::new(file, BytesCodec::new()).map_ok(|r| r.freeze());
 
     let mut stats = TransferStats::default();
     reqwest::Body::wrap_stream(ReadProgressStream::new(
         stream,
-        Box::new(move |progress_chunk, _progress_total| {
-            stats.record_chunk_transfer(progress_chunk as usize);
+        Box::new(move |progress_chunk, progress_total| {
+            let updated = stats.record_chunk_transfer(progress_chunk as usize);
+            // 按统计周期发送进度，最后一块总是发送
+            if let (true, Some(progress)) = (updated || progress_total >= total, &progress) {
+                progress.report(TransferKind::Upload, &stats, total);
+            }
         }),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::RetryPolicy;
+    use crate::test_support::{temp_file, StubResponse, StubServer};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn upload_file_sends_full_body_on_every_attempt() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let path = temp_file("png", &data);
+        let server = StubServer::sequence(vec![
+            StubResponse::new(500),
+            StubResponse::new(201).body("{}"),
+        ]);
+        let config = HttpConfig {
+            retry: RetryPolicy {
+                base_delay: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            },
+            ..HttpConfig::default()
+        };
+
+        let response = upload_file(
+            &server.url,
+            &path.to_string_lossy(),
+            "POST",
+            HashMap::new(),
+            &config,
+            None,
+        )
+        .await;
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(response.unwrap().body, "{}");
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for request in requests {
+            assert_eq!(
+                (request.method.as_str(), request.path.as_str()),
+                ("POST", "/")
+            );
+            assert_eq!(request.body, data);
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use crate::file::get_file_extension;
+use crate::image_processor::compressors::{
+    create_compression_options_from_store, process_compression_tasks, CompressionContext,
+    LOCAL_SUPPORTED_EXTS,
+};
+use glob::Pattern;
+use log::{error, info};
+use nanoid::nanoid;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Runtime, State};
+use tauri_plugin_store::StoreExt;
+
+// 文件大小在该时间内不再变化才认为写入完成
+const SETTLE_DELAY: Duration = Duration::from_millis(1000);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// PicSharp 写入的文件在该时间内触发的事件会被忽略
+const SELF_WRITE_TTL: Duration = Duration::from_secs(30);
+
+// PicSharp 自身最近写入的文件，避免监听到自己的输出后循环压缩
+static SELF_WRITTEN: Mutex<Vec<(PathBuf, Instant)>> = Mutex::new(Vec::new());
+
+fn normalize_path(path: &Path) -> PathBuf {
+    dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 记录 PicSharp 刚写入的文件
+pub fn mark_self_written(path: &Path) {
+    let mut written = SELF_WRITTEN.lock().unwrap_or_else(|e| e.into_inner());
+    written.retain(|(_, at)| at.elapsed() < SELF_WRITE_TTL);
+    written.push((normalize_path(path), Instant::now()));
+}
+
+fn is_self_written(path: &Path) -> bool {
+    let path = normalize_path(path);
+    let written = SELF_WRITTEN.lock().unwrap_or_else(|e| e.into_inner());
+    written
+        .iter()
+        .any(|(p, at)| p == &path && at.elapsed() < SELF_WRITE_TTL)
+}
+
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    // 忽略规则，匹配路径中的任意一级目录/文件名，或相对监听目录的路径
+    pub ignores: Vec<String>,
+    pub valid_exts: Vec<String>,
+    pub settle_delay: Duration,
+    // 是否同时处理已有图片被修改的情况，默认只处理新增图片
+    pub include_modified: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            ignores: vec![".git".to_string(), "node_modules".to_string()],
+            valid_exts: LOCAL_SUPPORTED_EXTS.iter().map(|s| s.to_string()).collect(),
+            settle_delay: SETTLE_DELAY,
+            include_modified: false,
+        }
+    }
+}
+
+struct IgnoreMatcher {
+    root: PathBuf,
+    patterns: Vec<Pattern>,
+}
+
+impl IgnoreMatcher {
+    fn new(root: &Path, ignores: &[String]) -> Self {
+        let patterns = ignores
+            .iter()
+            .filter_map(|ignore| match Pattern::new(ignore) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    error!("[watcher] -> Invalid ignore pattern <{}>: {}", ignore, e);
+                    None
+                }
+            })
+            .collect();
+        Self {
+            root: root.to_path_buf(),
+            patterns,
+        }
+    }
+
+    fn is_ignored(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        self.patterns.iter().any(|pattern| {
+            pattern.matches_path(relative)
+                || relative
+                    .components()
+                    .any(|c| pattern.matches(&c.as_os_str().to_string_lossy()))
+        })
+    }
+}
+
+enum WatchMessage {
+    Event(notify::Result<Event>),
+    Stop,
+}
+
+struct PendingFile {
+    size: u64,
+    changed_at: Instant,
+}
+
+/// 监听目录下新增的图片，文件写入完成后批量回调
+pub struct FolderWatcher {
+    root: PathBuf,
+    watcher: RecommendedWatcher,
+    sender: Sender<WatchMessage>,
+}
+
+impl FolderWatcher {
+    pub fn start<F>(root: &Path, options: WatchOptions, on_files: F) -> notify::Result<Self>
+    where
+        F: Fn(Vec<PathBuf>) + Send + 'static,
+    {
+        let root = normalize_path(root);
+        let (sender, receiver) = mpsc::channel();
+
+        let event_sender = sender.clone();
+        let mut watcher = notify::recommended_watcher(move |res| {
+            let _ = event_sender.send(WatchMessage::Event(res));
+        })?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        let matcher = IgnoreMatcher::new(&root, &options.ignores);
+        // 工作线程不 join，停止时发送信号后由其自行退出
+        thread::spawn(move || {
+            let mut pending: HashMap<PathBuf, PendingFile> = HashMap::new();
+            loop {
+                match receiver.recv_timeout(POLL_INTERVAL) {
+                    Ok(WatchMessage::Event(Ok(event))) => {
+                        let is_new = matches!(
+                            event.kind,
+                            EventKind::Create(_)
+                                | EventKind::Modify(ModifyKind::Name(
+                                    RenameMode::To | RenameMode::Both | RenameMode::Any
+                                ))
+                        ) || (options.include_modified
+                            && matches!(
+                                event.kind,
+                                EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any)
+                            ));
+                        for path in event.paths {
+                            // 写入中的文件会持续触发修改事件，重新计时
+                            if let Some(file) = pending.get_mut(&path) {
+                                file.changed_at = Instant::now();
+                                continue;
+                            }
+                            let ext = get_file_extension(&path);
+                            if !is_new
+                                || !path.is_file()
+                                || !options.valid_exts.iter().any(|valid| valid == &ext)
+                                || matcher.is_ignored(&path)
+                                || is_self_written(&path)
+                            {
+                                continue;
+                            }
+                            pending.insert(
+                                path,
+                                PendingFile {
+                                    size: 0,
+                                    changed_at: Instant::now(),
+                                },
+                            );
+                        }
+                    }
+                    Ok(WatchMessage::Event(Err(e))) => {
+                        error!("[watcher] -> Watch error: {}", e);
+                    }
+                    Ok(WatchMessage::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+                    Err(RecvTimeoutError::Timeout) => {}
+                }
+
+                // 大小稳定且可以打开的文件才认为写入完成
+                let mut ready = Vec::new();
+                pending.retain(|path, file| {
+                    if file.changed_at.elapsed() < options.settle_delay {
+                        return true;
+                    }
+                    let Ok(md) = fs::metadata(path) else {
+                        return false;
+                    };
+                    if md.len() != file.size || md.len() == 0 {
+                        file.size = md.len();
+                        file.changed_at = Instant::now();
+                        return true;
+                    }
+                    if File::open(path).is_err() {
+                        file.changed_at = Instant::now();
+                        return true;
+                    }
+                    if !is_self_written(path) {
+                        ready.push(path.clone());
+                    }
+                    false
+                });
+                if !ready.is_empty() {
+                    on_files(ready);
+                }
+            }
+        });
+
+        info!("[watcher] -> Start watching <{}>", root.display());
+        Ok(Self {
+            root,
+            watcher,
+            sender,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 停止监听，不等待正在进行的压缩批次完成
+    pub fn stop(mut self) {
+        let _ = self.watcher.unwatch(&self.root);
+        let _ = self.sender.send(WatchMessage::Stop);
+        info!("[watcher] -> Stop watching <{}>", self.root.display());
+    }
+}
+
+/// 应用中正在运行的监听任务，以 watch id 为键
+#[derive(Default)]
+pub struct WatcherRegistry {
+    watchers: Mutex<HashMap<String, FolderWatcher>>,
+}
+
+fn get_watch_options<R: Runtime>(app: &AppHandle<R>) -> WatchOptions {
+    let mut options = WatchOptions::default();
+    if let Ok(store) = app.store("settings.json") {
+        if let Some(ignores) = store
+            .get("compression_watch_file_ignore")
+            .and_then(|v| serde_json::from_value(v).ok())
+        {
+            options.ignores = ignores;
+        }
+    }
+    options
+}
+
+#[tauri::command]
+pub async fn ipc_watch_start<R: Runtime>(
+    app: AppHandle<R>,
+    webview_window: tauri::WebviewWindow,
+    registry: State<'_, WatcherRegistry>,
+    path: String,
+) -> Result<String, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Directory not found: {}", path));
+    }
+
+    let watch_id = nanoid!();
+    let event_watch_id = watch_id.clone();
+    let handle = app.clone();
+    let watcher = FolderWatcher::start(&root, get_watch_options(&app), move |paths| {
+        info!("[watcher] -> New images: {:?}", paths);
+        let _ = webview_window.emit(
+            "watch-new-images",
+            serde_json::json!({ "watch_id": event_watch_id, "paths": paths }),
+        );
+        let options = match create_compression_options_from_store(&handle) {
+            Ok(options) => options,
+            Err(e) => {
+                error!("[watcher] -> Failed to read compression options: {}", e);
+                return;
+            }
+        };
+        let context = CompressionContext::from_app(&handle, None);
+        let results =
+            process_compression_tasks(&webview_window, paths, &context, Arc::new(options));
+        let _ = webview_window.emit("compression-completed", serde_json::json!(results));
+    })
+    .map_err(|e| e.to_string())?;
+
+    registry
+        .watchers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(watch_id.clone(), watcher);
+    Ok(watch_id)
+}
+
+#[tauri::command]
+pub async fn ipc_watch_stop(
+    registry: State<'_, WatcherRegistry>,
+    watch_id: String,
+) -> Result<(), String> {
+    let watcher = registry
+        .watchers
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&watch_id)
+        .ok_or_else(|| format!("Watcher not found: {}", watch_id))?;
+    watcher.stop();
+    Ok(())
+}
@@ -6,6 +6,7 @@ import useCompressionStore from '@/store/compression';
 import useSelector from '@/hooks/useSelector';
 import { SettingsKey, CompressionMode, CompressionOutputMode } from '@/constants';
 import { isValidArray, correctFloat, calProgress } from '@/utils';
+import { Tinify } from '@/utils/tinify';
 import Compressor from '@/utils/compressor';
 import { humanSize } from '@/utils/fs';
 import { isString } from 'radash';
@@ -187,7 +188,7 @@ function ToolbarCompress() {
         compressionLevel,
         compressionType,
         limitCompressRate: thresholdEnable ? thresholdValue : undefined,
-        tinifyApiKeys: tinypngApiKeys.map((key) => key.api_key),
+        tinifyApiKeys: (await Tinify.getApiKeys()).map((key) => key.api_key),
         save: {
           mode: outputMode,
           newFileSuffix: saveAsFileSuffix,
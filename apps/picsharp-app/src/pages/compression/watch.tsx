@@ -5,6 +5,7 @@ import WatchFileManager from './watch-file-manager';
 import { parsePaths, humanSize } from '@/utils/fs';
 import { VALID_IMAGE_EXTS } from '@/constants';
 import { isValidArray, correctFloat } from '@/utils';
+import { Tinify } from '@/utils/tinify';
 import Compressor, { ICompressor } from '@/utils/compressor';
 import { SettingsKey } from '@/constants';
 import { isString } from 'radash';
@@ -43,7 +44,6 @@ function CompressionWatch() {
       const { fileMap, eventEmitter } = useCompressionStore.getState();
 
       const {
-        [SettingsKey.TinypngApiKeys]: tinypngApiKeys,
         [SettingsKey.CompressionMode]: compressionMode,
         [SettingsKey.CompressionOutput]: outputMode,
         [SettingsKey.CompressionOutputSaveToFolder]: saveToFolder,
@@ -76,7 +76,7 @@ function CompressionWatch() {
         compressionLevel,
         compressionType,
         limitCompressRate: thresholdEnable ? thresholdValue : undefined,
-        tinifyApiKeys: tinypngApiKeys.map((key) => key.api_key),
+        tinifyApiKeys: (await Tinify.getApiKeys()).map((key) => key.api_key),
         save: {
           mode: outputMode,
           newFileSuffix: saveAsFileSuffix,
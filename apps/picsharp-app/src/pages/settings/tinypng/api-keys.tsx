@@ -49,16 +49,39 @@ import { Tooltip, TooltipContent, TooltipTrigger } from '@/components/ui/tooltip
 import { AppContext } from '@/routes';
 import { useReport } from '@/hooks/useReport';
 
+// 通过 Rust 端保存 key，设置中只记录 id 与打码后的 key，再补充用量等界面字段
+const addApiKeys = async (
+  items: Array<{ name: string; api_key: string; usage: number | string }>,
+) => {
+  const added = new Map<string, number | string>();
+  for (const item of items) {
+    const { id } = await Tinify.addApiKey(item.name, item.api_key);
+    added.set(id, item.usage);
+  }
+  const { init, set } = useSettingsStore.getState();
+  await init(true);
+  await set(
+    SettingsKey.TinypngApiKeys,
+    useSettingsStore.getState()[SettingsKey.TinypngApiKeys].map((item) =>
+      added.has(item.id)
+        ? { ...item, usage: added.get(item.id), status: 'valid', created_at: Date.now() }
+        : item,
+    ),
+  );
+};
+
 export default memo(function SettingsCompressionTinyPngApiKeys() {
   const t = useI18n();
-  const { tinypng_api_keys: tinypngApiKeys, set } = useSettingsStore(
-    useSelector([SettingsKey.TinypngApiKeys, 'set']),
-  );
+  const {
+    tinypng_api_keys: tinypngApiKeys,
+    set,
+    init,
+  } = useSettingsStore(useSelector([SettingsKey.TinypngApiKeys, 'set', 'init']));
   const [loading, setLoading] = useState(false);
   const { messageApi } = useContext(AppContext);
-  const handleDelete = async (apiKey: string) => {
-    const newApiKeys = tinypngApiKeys.filter((item) => item.api_key !== apiKey);
-    await set(SettingsKey.TinypngApiKeys, newApiKeys);
+  const handleDelete = async (id: string) => {
+    await Tinify.removeApiKey(id);
+    await init(true);
     messageApi?.success(t('delete_success'));
   };
 
@@ -136,7 +159,7 @@ export default memo(function SettingsCompressionTinyPngApiKeys() {
               okText: t('confirm'),
               cancelText: t('cancel'),
               onConfirm: async () => {
-                await handleDelete(row.original.api_key);
+                await handleDelete(row.original.id);
               },
             });
           }}
@@ -152,11 +175,14 @@ export default memo(function SettingsCompressionTinyPngApiKeys() {
       setLoading(true);
       const newApiKeys = await Promise.all(
         tinypngApiKeys.map(async (item) => {
-          const { ok, compressionCount = '-' } = await Tinify.validate(item.api_key);
+          const { valid, compression_count } = await Tinify.validateKey(item.id).catch(() => ({
+            valid: false,
+            compression_count: undefined,
+          }));
           return {
             ...item,
-            usage: compressionCount,
-            status: ok ? 'valid' : 'invalid',
+            usage: compression_count ?? '-',
+            status: valid ? 'valid' : 'invalid',
           };
         }),
       );
@@ -261,10 +287,11 @@ const nameValidationStrategy: ValidationStrategy = {
   },
 };
 
-// API Key重复校验策略
+// API Key重复校验策略，设置中的 key 已打码，需与 Rust 端取出的明文比较
 const apiKeyValidationStrategy: ValidationStrategy = {
-  async validate(data, tinypngApiKeys) {
-    const exists = tinypngApiKeys.findIndex((item) => item.api_key === data.api_key) !== -1;
+  async validate(data) {
+    const apiKeys = await Tinify.getApiKeys();
+    const exists = apiKeys.findIndex((item) => item.api_key === data.api_key.trim()) !== -1;
     return {
       isValid: !exists,
       error: exists
@@ -280,7 +307,9 @@ const apiKeyValidationStrategy: ValidationStrategy = {
 // API Key有效性校验策略
 const apiKeyValidityStrategy: ValidationStrategy = {
   async validate(data) {
-    const { ok, compressionCount = 0 } = await Tinify.validate(data.api_key);
+    const { valid: ok, compression_count: compressionCount = 0 } = await Tinify.validateKey(
+      data.api_key,
+    ).catch(() => ({ valid: false, compression_count: 0 }));
     return {
       isValid: ok,
       error: !ok
@@ -325,8 +354,8 @@ function AddApiKeyDialog() {
   const [isLoading, setIsLoading] = useState(false);
   const t = useI18n();
   const r = useReport();
-  const { tinypng_api_keys: tinypngApiKeys, set } = useSettingsStore(
-    useSelector([SettingsKey.TinypngApiKeys, 'set']),
+  const { tinypng_api_keys: tinypngApiKeys } = useSettingsStore(
+    useSelector([SettingsKey.TinypngApiKeys]),
   );
   const { messageApi } = useContext(AppContext);
   const form = useForm<TinypngApiKeyFormData>({
@@ -350,13 +379,10 @@ function AddApiKeyDialog() {
       return;
     }
 
-    await set(SettingsKey.TinypngApiKeys, [
-      ...tinypngApiKeys,
+    await addApiKeys([
       {
         ...data,
         usage: Number(validationResult?.payload?.api_key_validity?.usage || 0) || '-',
-        status: 'valid',
-        created_at: Date.now(),
       },
     ]);
     setIsSubmiting(false);
@@ -424,8 +450,7 @@ function AddApiKeyDialog() {
                 });
                 return;
               }
-              await set(
-                SettingsKey.TinypngApiKeys,
+              await addApiKeys(
                 data.data.map((item) => ({
                   ...item,
                   usage:
@@ -433,8 +458,6 @@ function AddApiKeyDialog() {
                       validateResults.find((i) => i.api_key === item.api_key)?.payload
                         ?.api_key_validity?.usage || 0,
                     ) || '-',
-                  status: 'valid',
-                  created_at: Date.now(),
                 })),
               );
               messageApi?.success(t('import_success'));
@@ -461,8 +484,9 @@ function AddApiKeyDialog() {
             directory: true,
           });
           if (file) {
+            const apiKeys = await Tinify.getApiKeys();
             const content = JSON.stringify(
-              tinypngApiKeys.map((item: any) => ({
+              apiKeys.map((item) => ({
                 name: item.name,
                 api_key: item.api_key,
               })),
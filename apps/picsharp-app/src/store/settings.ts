@@ -53,6 +53,8 @@ interface SettingsState {
   [SettingsKey.CompressionWatermarkImageScale]: number;
   [SettingsKey.CompressionWatchFileIgnore]: string[];
   [SettingsKey.TinypngApiKeys]: Array<{
+    id: string;
+    // 存入安全存储后为打码后的值
     api_key: string;
     name: string;
     created_at: string;
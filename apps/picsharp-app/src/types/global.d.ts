@@ -22,6 +22,7 @@ declare global {
     name: string;
     path: string;
     parentDir: string;
+    rootDir: string;
     assetPath: string;
     bytesSize: number;
     formattedBytesSize: string;
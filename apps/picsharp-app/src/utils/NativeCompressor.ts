@@ -14,9 +14,24 @@ export namespace INativeCompressor {
     Failed = 'Failed',
   }
 
+  export enum SkipReason {
+    // 文件已是 PicSharp 以相同参数压缩的输出
+    AlreadyOptimized = 'AlreadyOptimized',
+    // 输出文件已存在且冲突策略为跳过
+    OutputExists = 'OutputExists',
+  }
+
+  // 跳过的结果序列化为 { Skipped: SkipReason }
+  export type CompressionResultStatus = CompressionStatus | { Skipped: SkipReason };
+
+  export function isSkipped(status: CompressionResultStatus): status is { Skipped: SkipReason } {
+    return typeof status === 'object' && status !== null && 'Skipped' in status;
+  }
+
   export interface CompressionResult {
     input_path: string;
-    status: CompressionStatus;
+    status: CompressionResultStatus;
+    original_bytes_size: number;
     output_path: string;
     output_path_converted: string;
     compressed_bytes_size: number;
@@ -68,6 +83,7 @@ export class NativeCompressor extends EventEmitter {
     filePaths: string[],
     onProgress?: INativeCompressor.CompressionProgressCallback,
     onCompleted?: INativeCompressor.CompressionCompletedCallback,
+    roots?: string[],
   ): Promise<void> {
     if (isFunction(onProgress)) {
       this.on(INativeCompressor.EventType.CompressionProgress, onProgress);
@@ -80,6 +96,7 @@ export class NativeCompressor extends EventEmitter {
     try {
       await invoke('ipc_compress_images', {
         paths: filePaths,
+        roots,
       });
     } catch (error) {
       console.error('Failed to compress images:', error);
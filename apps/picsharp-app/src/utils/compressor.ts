@@ -82,6 +82,7 @@ export namespace ICompressor {
       mode: CompressionOutputMode;
       new_file_suffix: string;
       new_folder_path: string;
+      root_dir?: string;
     };
     temp_dir?: string;
     convert_types?: ConvertFormat[];
@@ -525,6 +526,8 @@ export default class Compressor {
     ICompressor.CompressType,
     (file: FileInfo) => Promise<ICompressor.ResultItem>
   > = null;
+  // 输入路径 -> 拖入时的根目录，用于在新文件夹中保留目录结构
+  private rootDirs = new Map<string, string>();
 
   constructor(options?: ICompressor.Options) {
     this.options = Object.assign(
@@ -593,6 +596,11 @@ export default class Compressor {
     onFulfilled?: (res: ICompressor.ResultItem) => void,
     onRejected?: (res: ICompressor.FailedItem) => void,
   ): Promise<ICompressor.ResultItem[]> => {
+    for (const file of files) {
+      if (file.rootDir) {
+        this.rootDirs.set(file.path, file.rootDir);
+      }
+    }
     const scheduler = new Scheduler({
       concurrency: this.options.concurrency,
     })
@@ -625,6 +633,7 @@ export default class Compressor {
                 mode: this.options.save.mode,
                 new_file_suffix: this.options.save.newFileSuffix,
                 new_folder_path: this.options.save.newFolderPath,
+                root_dir: this.rootDirs.get(payload.input_path),
               },
               temp_dir: this.options.tempDir,
               convert_enable: this.options.convertEnable,
@@ -31,9 +31,12 @@ export interface ParsePathsItem {
   name: string;
   path: string;
   base_dir: string;
+  root_dir: string;
   bytes_size: number;
   disk_size: number;
   ext: string;
+  real_ext: string;
+  ext_mismatch: boolean;
   mime_type: string;
 }
 
@@ -50,6 +53,7 @@ export async function parsePaths(paths: string[], validExts: string[]) {
         assetPath: convertFileSrc(item.path),
         name: item.name,
         parentDir: item.base_dir,
+        rootDir: item.root_dir,
         bytesSize: item.bytes_size,
         formattedBytesSize: humanSize(item.bytes_size),
         diskSize: item.disk_size,
@@ -80,6 +84,15 @@ export async function countValidFiles(paths: string[], validExts: string[]) {
   return count;
 }
 
+export interface FixedExtension {
+  path: string;
+  fixed_path: string;
+}
+
+export async function fixFileExtensions(paths: string[]) {
+  return invoke<FixedExtension[]>('ipc_fix_file_extensions', { paths });
+}
+
 export function getFilename(path: string): string {
   if (typeof path !== 'string' || !path.trim()) {
     return '';
@@ -1,7 +1,7 @@
 import { upload } from '@tauri-apps/plugin-upload';
 import { isValidArray } from '@/utils';
 import { draw } from 'radash';
-import { fetch } from '@tauri-apps/plugin-http';
+import { invoke } from '@tauri-apps/api/core';
 
 export namespace ITinify {
   export interface ApiCompressResult {
@@ -21,6 +21,19 @@ export namespace ITinify {
   export interface CompressResult extends ApiCompressResult {
     id: string;
   }
+
+  // 设置中只保存打码后的 key，明文由 Rust 端从安全存储中取出
+  export interface ApiKeyEntry {
+    id: string;
+    name: string;
+    api_key: string;
+  }
+
+  export interface KeyValidation {
+    valid: boolean;
+    compression_count?: number;
+    error?: string;
+  }
 }
 
 export class Tinify {
@@ -57,33 +70,20 @@ export class Tinify {
     });
   }
 
-  static async validate(apiKey: string): Promise<{
-    ok: boolean;
-    compressionCount?: string;
-  }> {
-    try {
-      const url = `${Tinify.API_ENDPOINT}/shrink`;
-      const headers = new Headers({
-        Authorization: `Basic ${btoa(`api:${apiKey}`)}`,
-        'Content-Type': 'application/json',
-      });
-      const result = await fetch(url, {
-        method: 'POST',
-        headers,
-      });
-      if (result.headers.has('compression-count')) {
-        return {
-          ok: true,
-          compressionCount: result.headers.get('compression-count'),
-        };
-      }
-      return {
-        ok: false,
-      };
-    } catch (error) {
-      return {
-        ok: false,
-      };
-    }
+  static async getApiKeys(): Promise<ITinify.ApiKeyEntry[]> {
+    return invoke('ipc_tinify_api_keys');
+  }
+
+  static async addApiKey(name: string, key: string): Promise<ITinify.ApiKeyEntry> {
+    return invoke('ipc_tinify_add_key', { name, key });
+  }
+
+  static async removeApiKey(id: string): Promise<void> {
+    return invoke('ipc_tinify_remove_key', { id });
+  }
+
+  // key 可以是明文或已保存 key 的 id
+  static async validateKey(key: string): Promise<ITinify.KeyValidation> {
+    return invoke('ipc_tinify_validate_key', { key });
   }
 }
@@ -15,6 +15,7 @@ const OptionsSchema = z
         mode: z.nativeEnum(SaveMode).optional().default(SaveMode.Overwrite),
         new_file_suffix: z.string().optional().default('_compressed'),
         new_folder_path: z.string().optional(),
+        root_dir: z.string().optional(),
       })
       .optional()
       .default({}),
@@ -56,6 +56,7 @@ export async function processPngLossless(payload: {
       mode: options.save.mode,
       new_file_suffix: options.save.new_file_suffix,
       new_folder_path: options.save.new_folder_path,
+      root_dir: options.save.root_dir,
     });
     const requiredTransformations =
       options.resize_enable ||
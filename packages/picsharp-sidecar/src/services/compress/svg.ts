@@ -41,6 +41,7 @@ export async function processSvg(payload: SvgTaskPayload) {
       mode: options.save.mode,
       new_file_suffix: options.save.new_file_suffix,
       new_folder_path: options.save.new_folder_path,
+      root_dir: options.save.root_dir,
     });
     const tempFilePath = await copyFileToTemp(input_path, options.temp_dir);
     if (availableCompressRate) {
@@ -190,6 +190,7 @@ export async function processTinyPng(payload: ImageTaskPayload) {
       mode: options.save.mode,
       new_file_suffix: options.save.new_file_suffix,
       new_folder_path: options.save.new_folder_path,
+      root_dir: options.save.root_dir,
     });
 
     const originalTempPath = await copyFileToTemp(input_path, options.temp_dir);
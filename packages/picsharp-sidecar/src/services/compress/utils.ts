@@ -83,6 +83,7 @@ export async function saveImage(
     mode: options.save.mode,
     new_file_suffix: options.save.new_file_suffix,
     new_folder_path: options.save.new_folder_path,
+    root_dir: options.save.root_dir,
   });
   // 复制原始文件到临时文件
   const originalEctypePath = await copyFileToTemp(inputPath, options.temp_dir);
@@ -137,6 +138,7 @@ export async function processImage(
     mode: options.save.mode,
     new_file_suffix: options.save.new_file_suffix,
     new_folder_path: options.save.new_folder_path,
+    root_dir: options.save.root_dir,
   });
   const convert_results: any[] = await applyImageConversion(
     transformer,
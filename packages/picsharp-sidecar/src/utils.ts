@@ -71,6 +71,8 @@ export const createOutputPath = async (
     mode: 'overwrite' | 'save_as_new_file' | 'save_to_new_folder';
     new_file_suffix?: string;
     new_folder_path?: string;
+    // 拖入路径的所在目录，输出到新文件夹时保留文件相对于它的目录结构
+    root_dir?: string;
   },
 ) => {
   switch (options.mode) {
@@ -86,7 +88,16 @@ export const createOutputPath = async (
         throw new Error(`Directory '${options.new_folder_path}' does not exist`);
       }
       const filename = path.basename(inputPath);
-      return path.join(options.new_folder_path!, filename);
+      const relativeDir = options.root_dir
+        ? path.relative(options.root_dir, path.dirname(inputPath))
+        : '';
+      // 不在拖入目录下的文件直接输出到新文件夹
+      if (!relativeDir || relativeDir.startsWith('..') || path.isAbsolute(relativeDir)) {
+        return path.join(options.new_folder_path!, filename);
+      }
+      const outputDir = path.join(options.new_folder_path!, relativeDir);
+      await fs.mkdir(outputDir, { recursive: true });
+      return path.join(outputDir, filename);
     }
   }
 };